@@ -0,0 +1,220 @@
+use crate::Error;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{read_to_string, write};
+use std::io::ErrorKind;
+use std::path::Path;
+
+/// Known correct answers by (day, part).
+///
+/// Stored as a small subset of TOML, one table per day:
+///
+/// ```toml
+/// [day1]
+/// part1 = "1162"
+/// part2 = "1190"
+/// ```
+#[derive(PartialEq, Debug, Default)]
+pub struct Answers {
+    answers: BTreeMap<(u8, u8), String>,
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Verdict {
+    Pass,
+    Fail,
+    Unknown,
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Verdict::Pass => "PASS",
+            Verdict::Fail => "FAIL",
+            Verdict::Unknown => "UNKNOWN",
+        })
+    }
+}
+
+fn unquote(value: &str) -> Result<String, String> {
+    let inner = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .ok_or_else(|| format!("expected a quoted string or an integer, got {}", value))?;
+
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') => out.push('\n'),
+                Some('r') => out.push('\r'),
+                Some('t') => out.push('\t'),
+                Some('"') => out.push('"'),
+                Some('\\') => out.push('\\'),
+                other => return Err(format!("unsupported escape \\{}", other.unwrap_or(' '))),
+            },
+            '"' => return Err("unescaped quote in string".to_owned()),
+            c => out.push(c),
+        }
+    }
+
+    Ok(out)
+}
+
+fn quote(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+impl Answers {
+    pub fn parse(input: &str) -> Result<Answers, Error> {
+        let mut answers = BTreeMap::new();
+        let mut day = None;
+
+        for (i, line) in input.lines().enumerate() {
+            let err = |msg: String| Error::Config(format!("answers line {}: {}", i + 1, msg));
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(table) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                let d = table
+                    .trim()
+                    .strip_prefix("day")
+                    .and_then(|d| d.parse::<u8>().ok())
+                    .ok_or_else(|| err(format!("expected [dayN], got [{}]", table)))?;
+                day = Some(d);
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| err(format!("expected key = value, got {}", line)))?;
+            let part = match key.trim() {
+                "part1" => 1,
+                "part2" => 2,
+                key => return Err(err(format!("unknown key {}", key))),
+            };
+            let value = value.trim();
+            let value = if !value.is_empty() && value.chars().all(|c| c.is_ascii_digit()) {
+                value.to_owned()
+            } else {
+                unquote(value).map_err(err)?
+            };
+            let day = day.ok_or_else(|| err("answer outside of a [dayN] table".to_owned()))?;
+
+            answers.insert((day, part), value);
+        }
+
+        Ok(Answers { answers })
+    }
+
+    /// Loads the answers file, a missing file is treated as one without any answers.
+    pub fn load(path: &Path) -> Result<Answers, Error> {
+        match read_to_string(path) {
+            Ok(input) => Answers::parse(&input),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(Answers::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        Ok(write(path, self.to_toml())?)
+    }
+
+    pub fn to_toml(&self) -> String {
+        let mut out =
+            "# Expected answers, checked with --verify and updated with --record\n".to_owned();
+        let mut current = None;
+        for ((day, part), answer) in &self.answers {
+            if current != Some(*day) {
+                out.push_str(&format!("\n[day{}]\n", day));
+                current = Some(*day);
+            }
+            out.push_str(&format!("part{} = {}\n", part, quote(answer)));
+        }
+        out
+    }
+
+    pub fn get(&self, day: u8, part: u8) -> Option<&str> {
+        self.answers.get(&(day, part)).map(String::as_str)
+    }
+
+    pub fn set(&mut self, day: u8, part: u8, answer: &str) {
+        self.answers.insert((day, part), answer.to_owned());
+    }
+
+    pub fn check(&self, day: u8, part: u8, answer: &str) -> Verdict {
+        match self.get(day, part) {
+            Some(expected) if expected == answer => Verdict::Pass,
+            Some(_) => Verdict::Fail,
+            None => Verdict::Unknown,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::answers::{Answers, Verdict};
+
+    fn answers() -> Answers {
+        let mut answers = Answers::default();
+        answers.set(1, 1, "1162");
+        answers.set(1, 2, "1190");
+        answers.set(13, 2, "\n#.\n.#\n");
+        answers
+    }
+
+    #[test]
+    fn test_parse() {
+        let res = Answers::parse(
+            "# comment
+
+[day1]
+part1 = \"1162\"
+part2 = 1190
+
+[day13]
+part2 = \"\\n#.\\n.#\\n\"
+",
+        );
+
+        assert_eq!(answers(), res.unwrap());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        assert_eq!(answers(), Answers::parse(&answers().to_toml()).unwrap());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Answers::parse("part1 = \"1\"\n").is_err());
+        assert!(Answers::parse("[dayone]\n").is_err());
+        assert!(Answers::parse("[day1]\npart3 = \"1\"\n").is_err());
+        assert!(Answers::parse("[day1]\npart1 = \"1\n").is_err());
+    }
+
+    #[test]
+    fn test_check() {
+        let answers = answers();
+
+        assert_eq!(Verdict::Pass, answers.check(1, 1, "1162"));
+        assert_eq!(Verdict::Fail, answers.check(1, 2, "1191"));
+        assert_eq!(Verdict::Unknown, answers.check(2, 1, "150"));
+    }
+}
@@ -0,0 +1,323 @@
+use crate::input::InputSource;
+use crate::solver::Runner;
+use crate::{solver, Error};
+use std::collections::HashMap;
+use std::fs::{read_to_string, write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Timing statistics over all iterations of one stage, in nanoseconds.
+#[derive(PartialEq, Debug)]
+pub struct Stats {
+    pub min: f64,
+    pub median: f64,
+    pub mean: f64,
+    pub stddev: f64,
+}
+
+impl Stats {
+    pub fn from_samples(samples: &[Duration]) -> Stats {
+        let mut ns: Vec<f64> = samples.iter().map(|d| d.as_nanos() as f64).collect();
+        ns.sort_by(|a, b| a.total_cmp(b));
+
+        let n = ns.len() as f64;
+        let mean = ns.iter().sum::<f64>() / n;
+        let variance = ns.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+        let mid = ns.len() / 2;
+        let median = if ns.len().is_multiple_of(2) {
+            (ns[mid - 1] + ns[mid]) / 2.0
+        } else {
+            ns[mid]
+        };
+
+        Stats {
+            min: ns[0],
+            median,
+            mean,
+            stddev: variance.sqrt(),
+        }
+    }
+}
+
+pub struct Measurement {
+    pub day: u8,
+    pub stage: &'static str,
+    pub stats: Stats,
+}
+
+fn time<T>(iterations: usize, mut f: impl FnMut() -> Result<T, Error>) -> Result<Stats, Error> {
+    let mut samples = Vec::with_capacity(iterations);
+    for _ in 0..iterations {
+        let start = Instant::now();
+        f()?;
+        samples.push(start.elapsed());
+    }
+
+    Ok(Stats::from_samples(&samples))
+}
+
+/// Times parsing and both parts of a day, each stage is run `iterations` times.
+pub fn measure(
+    runner: &dyn Runner,
+    input: &str,
+    iterations: usize,
+) -> Result<Vec<Measurement>, Error> {
+    let parsed = runner.parse(input)?;
+    let day = runner.day();
+
+    Ok(vec![
+        Measurement {
+            day,
+            stage: "parse",
+            stats: time(iterations, || runner.parse(input))?,
+        },
+        Measurement {
+            day,
+            stage: "part1",
+            stats: time(iterations, || runner.part1(parsed.as_ref()))?,
+        },
+        Measurement {
+            day,
+            stage: "part2",
+            stats: time(iterations, || runner.part2(parsed.as_ref()))?,
+        },
+    ])
+}
+
+/// Median times by (day, stage), as stored in a baseline file.
+pub type Baseline = HashMap<(u8, String), f64>;
+
+pub fn to_baseline(measurements: &[Measurement]) -> String {
+    let mut out = "# day stage median_ns\n".to_owned();
+    for m in measurements {
+        out.push_str(&format!("{} {} {:.0}\n", m.day, m.stage, m.stats.median));
+    }
+    out
+}
+
+pub fn parse_baseline(input: &str) -> Result<Baseline, Error> {
+    let mut baseline = HashMap::new();
+    for line in input.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() != 3 {
+            return Err(Error::Config(format!("malformed baseline line '{}'", line)));
+        }
+        let median = fields[2]
+            .parse::<f64>()
+            .map_err(|e| Error::Config(format!("malformed baseline line '{}': {}", line, e)))?;
+        let day = fields[0]
+            .parse::<u8>()
+            .map_err(|e| Error::Config(format!("malformed baseline line '{}': {}", line, e)))?;
+        baseline.insert((day, fields[1].to_owned()), median);
+    }
+
+    Ok(baseline)
+}
+
+/// Relative change of the median against the baseline, in percent.
+pub fn change(m: &Measurement, baseline: &Baseline) -> Option<f64> {
+    baseline
+        .get(&(m.day, m.stage.to_owned()))
+        .filter(|base| **base > 0.0)
+        .map(|base| (m.stats.median - base) / base * 100.0)
+}
+
+fn human(ns: f64) -> String {
+    if ns >= 1e9 {
+        format!("{:.3}s", ns / 1e9)
+    } else if ns >= 1e6 {
+        format!("{:.3}ms", ns / 1e6)
+    } else if ns >= 1e3 {
+        format!("{:.3}µs", ns / 1e3)
+    } else {
+        format!("{:.0}ns", ns)
+    }
+}
+
+/// Runs the benchmark and prints the report, returns the number of failed days plus
+/// the number of stages that regressed past `threshold` percent.
+pub fn run(
+    days: &[u8],
+    input: &InputSource,
+    iterations: usize,
+    save: Option<&Path>,
+    compare: Option<&Path>,
+    threshold: f64,
+) -> Result<usize, Error> {
+    if iterations == 0 {
+        return Err(Error::Config("iterations must be at least 1".to_owned()));
+    }
+    if days.len() > 1 && input.is_single_day() {
+        return Err(Error::Config(
+            "--input must be a directory when benchmarking more than one day".to_owned(),
+        ));
+    }
+
+    let baseline = match compare {
+        Some(path) => {
+            let content = read_to_string(path).map_err(|e| {
+                Error::Config(format!("can't read baseline '{}': {}", path.display(), e))
+            })?;
+            Some(parse_baseline(&content)?)
+        }
+        None => None,
+    };
+
+    let mut failures = 0;
+    let mut measurements = vec![];
+    for day in days {
+        let runner = match solver::find(*day) {
+            Some(runner) => runner,
+            None => {
+                println!("day {}: failed: {}", day, Error::UnsupportedDay(*day));
+                failures += 1;
+                continue;
+            }
+        };
+        match input
+            .read(*day)
+            .and_then(|i| measure(runner.as_ref(), &i, iterations))
+        {
+            Ok(m) => measurements.extend(m),
+            Err(err) => {
+                println!("day {}: failed: {}", day, err);
+                failures += 1;
+            }
+        }
+    }
+
+    println!(
+        "{:>3} {:<6} {:>10} {:>10} {:>10} {:>10}{}",
+        "day",
+        "stage",
+        "min",
+        "median",
+        "mean",
+        "stddev",
+        if baseline.is_some() {
+            "     change"
+        } else {
+            ""
+        }
+    );
+    for m in &measurements {
+        let mut line = format!(
+            "{:>3} {:<6} {:>10} {:>10} {:>10} {:>10}",
+            m.day,
+            m.stage,
+            human(m.stats.min),
+            human(m.stats.median),
+            human(m.stats.mean),
+            human(m.stats.stddev)
+        );
+        if let Some(baseline) = &baseline {
+            match change(m, baseline) {
+                Some(c) if c > threshold => {
+                    line.push_str(&format!(" {:>+9.1}% REGRESSION", c));
+                    failures += 1;
+                }
+                Some(c) => line.push_str(&format!(" {:>+9.1}%", c)),
+                None => line.push_str("          -"),
+            }
+        }
+        println!("{}", line);
+    }
+
+    if let Some(path) = save {
+        write(path, to_baseline(&measurements))?;
+    }
+
+    Ok(failures)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bench::{change, parse_baseline, run, to_baseline, Measurement, Stats};
+    use crate::input::InputSource;
+    use crate::Error;
+    use std::path::Path;
+    use std::time::Duration;
+
+    fn measurement(day: u8, stage: &'static str, median: f64) -> Measurement {
+        Measurement {
+            day,
+            stage,
+            stats: Stats {
+                min: median,
+                median,
+                mean: median,
+                stddev: 0.0,
+            },
+        }
+    }
+
+    #[test]
+    fn test_stats() {
+        let samples: Vec<Duration> = [4, 2, 8, 6]
+            .iter()
+            .map(|v| Duration::from_nanos(*v))
+            .collect();
+
+        assert_eq!(
+            Stats {
+                min: 2.0,
+                median: 5.0,
+                mean: 5.0,
+                stddev: 5.0f64.sqrt(),
+            },
+            Stats::from_samples(&samples)
+        );
+    }
+
+    #[test]
+    fn test_stats_odd() {
+        let samples: Vec<Duration> = [3, 1, 2].iter().map(|v| Duration::from_nanos(*v)).collect();
+
+        assert_eq!(2.0, Stats::from_samples(&samples).median);
+    }
+
+    #[test]
+    fn test_baseline_round_trip() {
+        let measurements = vec![
+            measurement(1, "parse", 1500.0),
+            measurement(1, "part1", 20.0),
+        ];
+        let baseline = parse_baseline(&to_baseline(&measurements)).unwrap();
+
+        assert_eq!(Some(&1500.0), baseline.get(&(1, "parse".to_owned())));
+        assert_eq!(Some(&20.0), baseline.get(&(1, "part1".to_owned())));
+        assert_eq!(2, baseline.len());
+    }
+
+    #[test]
+    fn test_parse_baseline_malformed() {
+        assert!(parse_baseline("1 parse\n").is_err());
+        assert!(parse_baseline("1 parse fast\n").is_err());
+    }
+
+    #[test]
+    fn test_change() {
+        let baseline = parse_baseline("1 part1 100\n").unwrap();
+
+        assert_eq!(
+            Some(25.0),
+            change(&measurement(1, "part1", 125.0), &baseline)
+        );
+        assert_eq!(None, change(&measurement(1, "part2", 125.0), &baseline));
+    }
+
+    #[test]
+    fn test_missing_baseline() {
+        let input = InputSource::Path("input".into());
+        let missing = Path::new("missing/baseline.tsv");
+
+        match run(&[1], &input, 1, None, Some(missing), 10.0) {
+            Err(Error::Config(message)) => assert!(message.contains("missing/baseline.tsv")),
+            _ => panic!("expected a config error"),
+        }
+    }
+}
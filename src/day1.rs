@@ -1,15 +1,78 @@
+use crate::input::for_each_line;
+use crate::parse::{finish, finish_at, many_to_end, tag, IResult};
+use crate::solver::{parse_param, unknown_param, Solver};
 use crate::Error;
-use nom::bytes::complete::tag;
 use nom::character::complete::digit1;
 use nom::combinator::{map_res, recognize};
-use nom::multi::many0;
-use nom::IResult;
-use std::fs::read_to_string;
+use std::collections::VecDeque;
+use std::io::BufRead;
 
-pub fn calculate() -> Result<(String, String), Error> {
-    let input = parse_input(&read_to_string("input/day1")?)?;
+pub struct Day1 {
+    /// Number of depths summed into each window in part 2.
+    pub window: usize,
+    /// What the report flags as anomalies.
+    pub anomalies: Thresholds,
+}
 
-    Ok((part1(&input)?, part2(&input)?))
+impl Default for Day1 {
+    fn default() -> Self {
+        Day1 {
+            window: 3,
+            anomalies: Thresholds::default(),
+        }
+    }
+}
+
+impl Solver for Day1 {
+    type Input = Vec<u32>;
+
+    fn day(&self) -> u8 {
+        1
+    }
+
+    fn name(&self) -> &'static str {
+        "Sonar Sweep"
+    }
+
+    fn parse(&self, input: &str) -> Result<Self::Input, Error> {
+        parse_input(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Result<String, Error> {
+        part1(input)
+    }
+
+    fn part2(&self, input: &Self::Input) -> Result<String, Error> {
+        Ok(format!("{}", count_increases(input, self.window)))
+    }
+
+    fn params(&self) -> &'static [&'static str] {
+        &["window", "rolling", "sigma", "repeats"]
+    }
+
+    fn set_param(&mut self, key: &str, value: &str) -> Result<(), Error> {
+        match key {
+            "window" => self.window = parse_param(key, value, 1..=usize::MAX)?,
+            "rolling" => self.anomalies.rolling = parse_param(key, value, 2..=usize::MAX)?,
+            "sigma" => self.anomalies.sigma = parse_param(key, value, 0.1..=100.0)?,
+            "repeats" => self.anomalies.repeats = parse_param(key, value, 2..=usize::MAX)?,
+            _ => return Err(unknown_param(self.day(), key, self.params())),
+        }
+
+        Ok(())
+    }
+
+    fn stream(&self, reader: &mut dyn BufRead) -> Option<Result<(String, String), Error>> {
+        Some(stream(reader, self.window))
+    }
+
+    fn report(&self, input: &Self::Input) -> Option<Result<String, Error>> {
+        Some(Ok(report(input, self.window, &self.anomalies)))
+    }
+
+    fn plot(&self, input: &Self::Input, width: usize) -> Option<Result<String, Error>> {
+        Some(Ok(plot(input, self.window, width)))
+    }
 }
 
 fn my_u32(input: &str) -> IResult<&str, u32> {
@@ -20,55 +83,346 @@ fn my_u32(input: &str) -> IResult<&str, u32> {
 }
 
 fn multi(i: &str) -> IResult<&str, Vec<u32>> {
-    many0(my_u32)(i)
+    many_to_end(my_u32)(i)
 }
 
-fn parse_input(input: &str) -> Result<Vec<u32>, Error> {
-    let (_, data) = multi(input)?;
+pub fn parse_input(input: &str) -> Result<Vec<u32>, Error> {
+    finish(input, multi)
+}
 
-    Ok(data)
+pub fn part1(input: &[u32]) -> Result<String, Error> {
+    Ok(format!("{}", count_increases(input, 1)))
 }
 
-fn part1(input: &[u32]) -> Result<String, Error> {
-    let mut previous = None;
-    let mut num_increases = 0;
+pub fn part2(input: &[u32]) -> Result<String, Error> {
+    Ok(format!("{}", count_increases(input, 3)))
+}
 
-    for val in input {
-        match previous {
-            None => {}
-            Some(old) => {
-                if val > old {
-                    num_increases += 1;
-                }
+/// Counts how often the sum of `window` consecutive depths is larger than the sum of
+/// the window before it. Neighbouring windows share all but their outermost depths, so
+/// only those two have to be compared.
+pub fn count_increases(depths: &[u32], window: usize) -> usize {
+    depths
+        .iter()
+        .zip(depths.iter().skip(window))
+        .filter(|(first, last)| last > first)
+        .count()
+}
+
+/// Number of buckets in the delta histogram of [`Trend`].
+const HISTOGRAM_BUCKETS: i64 = 10;
+
+/// Statistics about the shape of the depth series. Line numbers start at 1.
+#[derive(PartialEq, Debug)]
+pub struct Trend {
+    /// First line and number of depths of the longest strictly increasing run, the
+    /// earliest one on a tie.
+    pub longest_run: Option<(usize, usize)>,
+    /// Line and size of the largest drop from one depth to the next.
+    pub largest_drop: Option<(usize, u32)>,
+    /// The differences between consecutive depths, as (lowest, highest, count).
+    pub histogram: Vec<(i64, i64, usize)>,
+}
+
+fn histogram(deltas: &[i64]) -> Vec<(i64, i64, usize)> {
+    let (min, max) = match (deltas.iter().min(), deltas.iter().max()) {
+        (Some(min), Some(max)) => (*min, *max),
+        _ => return vec![],
+    };
+    let width = (max - min) / HISTOGRAM_BUCKETS + 1;
+
+    let mut buckets: Vec<(i64, i64, usize)> = (0..=(max - min) / width)
+        .map(|i| (min + i * width, min + (i + 1) * width - 1, 0))
+        .collect();
+    for delta in deltas {
+        buckets[((delta - min) / width) as usize].2 += 1;
+    }
+
+    buckets
+}
+
+pub fn trend(depths: &[u32]) -> Trend {
+    let mut longest_run = None;
+    let mut run_start = 0;
+    for i in 0..=depths.len() {
+        if i == depths.len() || (i > 0 && depths[i] <= depths[i - 1]) {
+            if i > run_start && longest_run.is_none_or(|(_, length)| i - run_start > length) {
+                longest_run = Some((run_start + 1, i - run_start));
             }
+            run_start = i;
         }
-        previous = Some(val)
     }
 
-    Ok(format!("{}", num_increases))
+    let largest_drop = depths
+        .windows(2)
+        .enumerate()
+        .filter(|(_, pair)| pair[1] < pair[0])
+        .map(|(i, pair)| (i + 2, pair[0] - pair[1]))
+        .fold(None, |largest: Option<(usize, u32)>, drop| match largest {
+            Some(l) if l.1 >= drop.1 => Some(l),
+            _ => Some(drop),
+        });
+
+    let deltas: Vec<i64> = depths
+        .windows(2)
+        .map(|pair| pair[1] as i64 - pair[0] as i64)
+        .collect();
+
+    Trend {
+        longest_run,
+        largest_drop,
+        histogram: histogram(&deltas),
+    }
 }
 
-fn part2(input: &[u32]) -> Result<String, Error> {
-    let mut previous = None;
-    let mut num_increases = 0;
+/// When a depth counts as an anomaly.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Thresholds {
+    /// Number of preceding depths the rolling mean is taken over.
+    pub rolling: usize,
+    /// How many standard deviations from the rolling mean make an outlier.
+    pub sigma: f64,
+    /// How many identical depths in a row count as a dropout.
+    pub repeats: usize,
+}
 
-    for (i, _) in input.iter().enumerate() {
-        if i < 3 {
-            continue;
+impl Default for Thresholds {
+    fn default() -> Self {
+        Thresholds {
+            rolling: 10,
+            sigma: 3.0,
+            repeats: 3,
         }
+    }
+}
 
-        match previous {
-            None => {}
-            Some(old) => {
-                if input[i - 2] + input[i - 1] + input[i] > old {
-                    num_increases += 1;
-                }
+#[derive(PartialEq, Debug)]
+pub enum Anomaly {
+    /// Too far from the mean of the depths before it.
+    Outlier { mean: f64, deviation: f64 },
+    /// The sensor reported no depth at all.
+    Zero,
+    /// The same depth as the readings before it, as when the sensor is stuck.
+    Repeated,
+}
+
+/// Finds suspicious depths, as (line, depth, anomaly) with lines starting at 1. A depth
+/// gets only one anomaly, zeros take precedence over repeats and repeats over outliers.
+pub fn anomalies(depths: &[u32], thresholds: &Thresholds) -> Vec<(usize, u32, Anomaly)> {
+    let mut found = vec![];
+    let mut same = 0;
+    let (mut sum, mut squares) = (0f64, 0f64);
+
+    for (i, depth) in depths.iter().enumerate() {
+        same = match i {
+            0 => 1,
+            _ if depths[i - 1] == *depth => same + 1,
+            _ => 1,
+        };
+
+        if *depth == 0 {
+            found.push((i + 1, *depth, Anomaly::Zero));
+        } else if same >= thresholds.repeats {
+            found.push((i + 1, *depth, Anomaly::Repeated));
+        } else if i >= thresholds.rolling {
+            let n = thresholds.rolling as f64;
+            let mean = sum / n;
+            let deviation = (squares / n - mean * mean).max(0.0).sqrt();
+            if deviation > 0.0 && (*depth as f64 - mean).abs() > thresholds.sigma * deviation {
+                found.push((i + 1, *depth, Anomaly::Outlier { mean, deviation }));
             }
         }
-        previous = Some(input[i - 2] + input[i - 1] + input[i])
+
+        let value = *depth as f64;
+        sum += value;
+        squares += value * value;
+        if i >= thresholds.rolling {
+            let old = depths[i - thresholds.rolling] as f64;
+            sum -= old;
+            squares -= old * old;
+        }
     }
 
-    Ok(format!("{}", num_increases))
+    found
+}
+
+/// Describes the depth series: the increases for `window`, the [`Trend`] and the
+/// anomalies found with `thresholds`.
+pub fn report(depths: &[u32], window: usize, thresholds: &Thresholds) -> String {
+    let trend = trend(depths);
+    let mut report = format!(
+        "increases: {}\nincreases with window {}: {}\n",
+        count_increases(depths, 1),
+        window,
+        count_increases(depths, window)
+    );
+
+    match trend.longest_run {
+        Some((line, length)) => report.push_str(&format!(
+            "longest increasing run: {} depths from line {}\n",
+            length, line
+        )),
+        None => report.push_str("longest increasing run: none\n"),
+    }
+    match trend.largest_drop {
+        Some((line, drop)) => {
+            report.push_str(&format!("largest drop: {} at line {}\n", drop, line))
+        }
+        None => report.push_str("largest drop: none\n"),
+    }
+
+    report.push_str("deltas:\n");
+    let most = trend.histogram.iter().map(|b| b.2).max().unwrap_or(0);
+    for (low, high, count) in &trend.histogram {
+        let bar = if most == 0 {
+            0
+        } else {
+            (count * 40).div_ceil(most)
+        };
+        report.push_str(&format!(
+            "{:>7}..{:<7} {:<40} {}\n",
+            low,
+            high,
+            "#".repeat(bar),
+            count
+        ));
+    }
+
+    let found = anomalies(depths, thresholds);
+    report.push_str(&format!("anomalies: {}\n", found.len()));
+    for (line, depth, anomaly) in found {
+        let reason = match anomaly {
+            Anomaly::Outlier { mean, deviation } => format!(
+                "{:.1} standard deviations from the rolling mean {:.1}",
+                (depth as f64 - mean).abs() / deviation,
+                mean
+            ),
+            Anomaly::Zero => "zero depth".to_owned(),
+            Anomaly::Repeated => "repeated depth".to_owned(),
+        };
+        report.push_str(&format!("  line {}: {}, {}\n", line, depth, reason));
+    }
+
+    report
+}
+
+/// Number of rows in the chart drawn by [`plot`].
+const PLOT_HEIGHT: usize = 20;
+
+/// Draws the depths as a chart `width` characters wide, with the shallowest depth at
+/// the top. When there are more depths than columns every column covers a range of
+/// depths, drawn from its shallowest to its deepest. Below the chart a `^` marks the
+/// columns where the sum of `window` depths mostly increased.
+pub fn plot(depths: &[u32], window: usize, width: usize) -> String {
+    let (min, max) = match (depths.iter().min(), depths.iter().max()) {
+        (Some(min), Some(max)) => (*min as u64, *max as u64),
+        _ => return "no depths\n".to_owned(),
+    };
+    let label_width = max.to_string().len();
+    let columns = width.saturating_sub(label_width + 2).clamp(1, depths.len());
+    let row = |depth: u32| match max - min {
+        0 => 0,
+        span => ((depth as u64 - min) * (PLOT_HEIGHT as u64 - 1) / span) as usize,
+    };
+
+    let mut grid = vec![vec![' '; columns]; PLOT_HEIGHT];
+    let mut markers = String::new();
+    for column in 0..columns {
+        let range = column * depths.len() / columns..(column + 1) * depths.len() / columns;
+        let chunk = &depths[range.clone()];
+        let top = chunk.iter().map(|d| row(*d)).min().unwrap_or(0);
+        let bottom = chunk.iter().map(|d| row(*d)).max().unwrap_or(0);
+        for line in &mut grid[top..=bottom] {
+            line[column] = '#';
+        }
+
+        let compared = range.clone().filter(|i| *i >= window && window > 0);
+        let (increases, comparisons) = compared.fold((0, 0), |(increases, comparisons), i| {
+            (
+                increases + (depths[i] > depths[i - window]) as usize,
+                comparisons + 1,
+            )
+        });
+        markers.push(if increases * 2 > comparisons {
+            '^'
+        } else {
+            ' '
+        });
+    }
+
+    let mut chart = String::new();
+    for (i, line) in grid.iter().enumerate() {
+        let label = match i {
+            0 => min.to_string(),
+            _ if i == PLOT_HEIGHT - 1 => max.to_string(),
+            _ => String::new(),
+        };
+        let line: String = line.iter().collect();
+        chart.push_str(&format!(
+            "{:>w$} |{}\n",
+            label,
+            line.trim_end(),
+            w = label_width
+        ));
+    }
+    chart.push_str(&format!(
+        "{:>w$} +{}\n",
+        "",
+        "-".repeat(columns),
+        w = label_width
+    ));
+    chart.push_str(&format!(
+        "{:>w$}  {}\n",
+        "",
+        markers.trim_end(),
+        w = label_width
+    ));
+
+    let last = depths.len().to_string();
+    chart.push_str(&format!(
+        "{:>w$}  1{:>pad$}\n",
+        "",
+        if columns > last.len() {
+            last
+        } else {
+            String::new()
+        },
+        w = label_width,
+        pad = columns.saturating_sub(1)
+    ));
+
+    chart
+}
+
+/// Solves both parts in one pass, only the depths of the current window are kept.
+pub fn stream(reader: &mut dyn BufRead, window: usize) -> Result<(String, String), Error> {
+    let mut previous = None;
+    let mut increases = 0u64;
+    let mut recent = VecDeque::with_capacity(window);
+    let mut window_increases = 0u64;
+
+    for_each_line(reader, |number, line| {
+        let depth = finish_at(number, line, my_u32)?;
+        match previous {
+            Some(old) if depth > old => increases += 1,
+            _ => {}
+        }
+        previous = Some(depth);
+
+        if recent.len() == window {
+            match recent.pop_front() {
+                Some(first) if depth > first => window_increases += 1,
+                _ => {}
+            }
+        }
+        if window > 0 {
+            recent.push_back(depth);
+        }
+
+        Ok(())
+    })?;
+
+    Ok((increases.to_string(), window_increases.to_string()))
 }
 
 #[test]
@@ -93,18 +447,134 @@ pub fn test_parse() {
     );
 }
 
+#[test]
+pub fn test_parse_rejects_garbage_line() {
+    match parse_input("199\n200\nabc\n208\n") {
+        Err(Error::Parse(location)) => {
+            assert_eq!(3, location.line);
+            assert_eq!(1, location.column);
+        }
+        _ => panic!("expected a parse error"),
+    }
+}
+
 #[test]
 pub fn test_part1() {
     assert_eq!(
         "7",
-        part1(&vec![199, 200, 208, 210, 200, 207, 240, 269, 260, 263]).unwrap()
+        part1(&[199, 200, 208, 210, 200, 207, 240, 269, 260, 263]).unwrap()
     );
 }
 
 #[test]
 pub fn test_part2() {
     assert_eq!(
-        "4",
-        part2(&vec![199, 200, 208, 210, 200, 207, 240, 269, 260, 263]).unwrap()
+        "5",
+        part2(&[199, 200, 208, 210, 200, 207, 240, 269, 260, 263]).unwrap()
     );
 }
+
+#[test]
+pub fn test_stream() {
+    let mut input = "199\n200\n208\n210\n200\n207\n240\n269\n260\n263\n".as_bytes();
+
+    assert_eq!(
+        ("7".to_owned(), "5".to_owned()),
+        stream(&mut input, 3).unwrap()
+    );
+}
+
+#[test]
+pub fn test_stream_reports_line() {
+    match stream(&mut "199\n200\nabc\n".as_bytes(), 3) {
+        Err(Error::Parse(location)) => assert_eq!(3, location.line),
+        _ => panic!("expected a parse error"),
+    }
+}
+
+#[test]
+pub fn test_count_increases() {
+    let depths = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+
+    assert_eq!(7, count_increases(&depths, 1));
+    assert_eq!(5, count_increases(&depths, 3));
+    assert_eq!(1, count_increases(&depths, 9));
+    assert_eq!(0, count_increases(&depths, 10));
+    assert_eq!(0, count_increases(&depths, 0));
+    assert_eq!(0, count_increases(&[], 3));
+}
+
+#[test]
+pub fn test_trend() {
+    let trend = trend(&[199, 200, 208, 210, 200, 207, 240, 269, 260, 263]);
+
+    assert_eq!(Some((1, 4)), trend.longest_run);
+    assert_eq!(Some((5, 10)), trend.largest_drop);
+    assert_eq!(9, trend.histogram.iter().map(|b| b.2).sum::<usize>());
+    assert_eq!((-10, -6, 2), trend.histogram[0]);
+}
+
+#[test]
+pub fn test_trend_without_depths() {
+    assert_eq!(
+        Trend {
+            longest_run: None,
+            largest_drop: None,
+            histogram: vec![],
+        },
+        trend(&[])
+    );
+}
+
+#[test]
+pub fn test_anomalies() {
+    let mut depths = vec![100, 102, 101, 103, 102, 104, 103, 105];
+    depths.extend([500, 104, 0, 106, 106, 106, 107]);
+
+    let thresholds = Thresholds {
+        rolling: 4,
+        sigma: 3.0,
+        repeats: 3,
+    };
+    let found = anomalies(&depths, &thresholds);
+
+    assert_eq!(
+        vec![9, 11, 14],
+        found.iter().map(|a| a.0).collect::<Vec<usize>>()
+    );
+    assert!(matches!(found[0].2, Anomaly::Outlier { .. }));
+    assert_eq!(Anomaly::Zero, found[1].2);
+    assert_eq!(Anomaly::Repeated, found[2].2);
+}
+
+#[test]
+pub fn test_anomalies_on_the_example() {
+    let depths = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+
+    assert_eq!(
+        Vec::<(usize, u32, Anomaly)>::new(),
+        anomalies(&depths, &Thresholds::default())
+    );
+}
+
+#[test]
+pub fn test_plot() {
+    let chart = plot(&[199, 200, 208, 210, 200, 207, 240, 269, 260, 263], 1, 80);
+    let lines: Vec<&str> = chart.lines().collect();
+
+    assert_eq!(PLOT_HEIGHT + 3, lines.len());
+    assert_eq!("199 |##  #", lines[0]);
+    assert_eq!("269 |       #", lines[PLOT_HEIGHT - 1]);
+    assert_eq!("    +----------", lines[PLOT_HEIGHT]);
+    assert_eq!("      ^^^ ^^^ ^", lines[PLOT_HEIGHT + 1]);
+    assert_eq!("     1       10", lines[PLOT_HEIGHT + 2]);
+}
+
+#[test]
+pub fn test_plot_downsamples_to_width() {
+    let depths: Vec<u32> = (1..=1000).collect();
+    let chart = plot(&depths, 3, 40);
+
+    assert!(chart.lines().all(|line| line.len() <= 40));
+    assert_eq!(PLOT_HEIGHT + 3, chart.lines().count());
+}
@@ -1,19 +1,45 @@
 use crate::day10::Command::*;
+use crate::input::for_each_line;
+use crate::parse::{expect, finish, finish_at, many_to_end, tag, IResult};
+use crate::solver::Solver;
 use crate::Error;
-use nom::bytes::complete::tag;
-use nom::bytes::complete::take_until;
+use nom::character::complete::anychar;
+use nom::combinator::map_res;
 use nom::multi::many0;
-use nom::IResult;
-use std::fs::read_to_string;
+use std::io::BufRead;
 
-pub fn calculate() -> Result<(String, String), Error> {
-    let input = parse_input(&read_to_string("input/day10")?)?;
+pub struct Day10;
 
-    Ok((part1(&input)?, part2(&input)?))
+impl Solver for Day10 {
+    type Input = Vec<Vec<Command>>;
+
+    fn day(&self) -> u8 {
+        10
+    }
+
+    fn name(&self) -> &'static str {
+        "Syntax Scoring"
+    }
+
+    fn parse(&self, input: &str) -> Result<Self::Input, Error> {
+        parse_input(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Result<String, Error> {
+        part1(input)
+    }
+
+    fn part2(&self, input: &Self::Input) -> Result<String, Error> {
+        part2(input)
+    }
+
+    fn stream(&self, reader: &mut dyn BufRead) -> Option<Result<(String, String), Error>> {
+        Some(stream(reader))
+    }
 }
 
 #[derive(PartialEq, Clone, Debug)]
-enum Command {
+pub enum Command {
     PushA,
     PopA,
     PushB,
@@ -31,9 +57,11 @@ enum ValidationError {
     StackUnderFlow,
 }
 
-impl From<char> for Command {
-    fn from(c: char) -> Self {
-        match c {
+impl TryFrom<char> for Command {
+    type Error = String;
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        Ok(match c {
             '<' => Command::PushA,
             '>' => Command::PopA,
             '[' => Command::PushB,
@@ -42,26 +70,28 @@ impl From<char> for Command {
             '}' => Command::PopC,
             '(' => Command::PushP,
             ')' => Command::PopP,
-            _ => panic!("illegal input"),
-        }
+            _ => return Err(format!("'{}' is not a bracket", c)),
+        })
     }
 }
 
+fn command(input: &str) -> IResult<&str, Command> {
+    map_res(anychar, Command::try_from)(input)
+}
+
 fn my_str(input: &str) -> IResult<&str, Vec<Command>> {
-    let (rest, data) = take_until("\n")(input)?;
-    let (rest, _) = tag("\n")(rest)?;
+    let (rest, data) = many0(command)(input)?;
+    let (rest, _) = expect(tag("\n"), "a bracket or a line ending")(rest)?;
 
-    Ok((rest, data.chars().map(Command::from).collect()))
+    Ok((rest, data))
 }
 
 fn multi(i: &str) -> IResult<&str, Vec<Vec<Command>>> {
-    many0(my_str)(i)
+    many_to_end(my_str)(i)
 }
 
-fn parse_input(input: &str) -> Result<Vec<Vec<Command>>, Error> {
-    let (_, data) = multi(input)?;
-
-    Ok(data)
+pub fn parse_input(input: &str) -> Result<Vec<Vec<Command>>, Error> {
+    finish(input, multi)
 }
 
 fn validate(commands: &[Command]) -> Result<(), ValidationError> {
@@ -116,46 +146,84 @@ fn validate(commands: &[Command]) -> Result<(), ValidationError> {
     }
 }
 
-fn part1(input: &[Vec<Command>]) -> Result<String, Error> {
-    let mut result = 0;
-    for c in input {
-        if let Err(ValidationError::Corrupted(val)) = validate(c) {
-            match val {
-                PopA => result += 25137,
-                PopB => result += 57,
-                PopC => result += 1197,
-                PopP => result += 3,
-                _ => {}
-            }
-        }
+fn corrupted_score(commands: &[Command]) -> u64 {
+    match validate(commands) {
+        Err(ValidationError::Corrupted(PopA)) => 25137,
+        Err(ValidationError::Corrupted(PopB)) => 57,
+        Err(ValidationError::Corrupted(PopC)) => 1197,
+        Err(ValidationError::Corrupted(PopP)) => 3,
+        _ => 0,
     }
+}
+
+pub fn part1(input: &[Vec<Command>]) -> Result<String, Error> {
+    let result: u64 = input.iter().map(|c| corrupted_score(c)).sum();
     Ok(format!("{}", result))
 }
 
-fn score_stack(stack: &[Command]) -> u64 {
-    let mut score = 0;
+/// Scores the closing sequence for `stack`, `None` if the score doesn't fit in an u64.
+fn score_stack(stack: &[Command]) -> Option<u64> {
+    let mut score: u64 = 0;
     for s in stack {
-        match s {
-            PushA => score = score * 5 + 4,
-            PushB => score = score * 5 + 2,
-            PushC => score = score * 5 + 3,
-            PushP => score = score * 5 + 1,
-            _ => {}
-        }
+        let value = match s {
+            PushA => 4,
+            PushB => 2,
+            PushC => 3,
+            PushP => 1,
+            _ => continue,
+        };
+        score = score.checked_mul(5)?.checked_add(value)?;
     }
-    score
+    Some(score)
 }
 
-fn part2(input: &[Vec<Command>]) -> Result<String, Error> {
+/// The completion score of `commands`, `None` if the line isn't incomplete.
+fn completion_score(commands: &[Command]) -> Result<Option<u64>, Error> {
+    if let Err(ValidationError::Incomplete(stack)) = validate(commands) {
+        let stack: Vec<Command> = stack.into_iter().rev().collect();
+        score_stack(&stack).map(Some).ok_or_else(|| {
+            Error::InvalidState("completion score doesn't fit in 64 bits".to_owned())
+        })
+    } else {
+        Ok(None)
+    }
+}
+
+fn middle_score(mut scores: Vec<u64>) -> Result<String, Error> {
+    scores.sort_unstable();
+    match scores.get(scores.len() / 2) {
+        Some(score) => Ok(format!("{}", score)),
+        None => Err(Error::NoSolution("there are no incomplete lines")),
+    }
+}
+
+pub fn part2(input: &[Vec<Command>]) -> Result<String, Error> {
     let mut result = vec![];
     for c in input {
-        if let Err(ValidationError::Incomplete(stack)) = validate(c) {
-            let stack: Vec<Command> = stack.into_iter().rev().collect();
-            result.push(score_stack(&stack));
+        if let Some(score) = completion_score(c)? {
+            result.push(score);
         }
     }
-    result.sort_unstable();
-    Ok(format!("{}", result[result.len() / 2]))
+    middle_score(result)
+}
+
+/// Solves both parts in one pass. Only one score per incomplete line is kept, as the
+/// middle one can't be found without seeing them all.
+pub fn stream(reader: &mut dyn BufRead) -> Result<(String, String), Error> {
+    let mut corrupted = 0u64;
+    let mut incomplete = vec![];
+
+    for_each_line(reader, |number, line| {
+        let commands = finish_at(number, line, my_str)?;
+        corrupted += corrupted_score(&commands);
+        if let Some(score) = completion_score(&commands)? {
+            incomplete.push(score);
+        }
+
+        Ok(())
+    })?;
+
+    Ok((corrupted.to_string(), middle_score(incomplete)?))
 }
 
 #[cfg(test)]
@@ -164,6 +232,7 @@ mod tests {
     use crate::day10::part1;
     use crate::day10::part2;
     use crate::day10::score_stack;
+    use crate::day10::stream;
     use crate::day10::validate;
     use crate::day10::Command;
     use crate::day10::Command::*;
@@ -259,28 +328,22 @@ mod tests {
     #[test]
     pub fn test_score_stack() {
         assert_eq!(
-            288957,
-            score_stack(&vec![
-                PushC, PushC, PushB, PushB, PushP, PushC, PushP, PushB
-            ])
+            Some(288957),
+            score_stack(&[PushC, PushC, PushB, PushB, PushP, PushC, PushP, PushB])
         );
         assert_eq!(
-            5566,
-            score_stack(&vec![PushP, PushC, PushA, PushB, PushC, PushP])
+            Some(5566),
+            score_stack(&[PushP, PushC, PushA, PushB, PushC, PushP])
         );
         assert_eq!(
-            1480781,
-            score_stack(&vec![
-                PushC, PushC, PushA, PushC, PushA, PushP, PushP, PushP, PushP
-            ])
+            Some(1480781),
+            score_stack(&[PushC, PushC, PushA, PushC, PushA, PushP, PushP, PushP, PushP])
         );
         assert_eq!(
-            995444,
-            score_stack(&vec![
-                PushB, PushB, PushC, PushC, PushB, PushC, PushB, PushC, PushA
-            ])
+            Some(995444),
+            score_stack(&[PushB, PushB, PushC, PushC, PushB, PushC, PushB, PushC, PushA])
         );
-        assert_eq!(294, score_stack(&vec![PushB, PushP, PushC, PushA]));
+        assert_eq!(Some(294), score_stack(&[PushB, PushP, PushC, PushA]));
     }
 
     #[test]
@@ -292,4 +355,25 @@ mod tests {
     pub fn test_part2() {
         assert_eq!("288957", part2(&test_input()).unwrap());
     }
+
+    #[test]
+    pub fn test_stream() {
+        let mut input = "[({(<(())[]>[[{[]{<()<>>
+[(()[<>])]({[<{<<[]>>(
+{([(<{}[<>[]}>{[]{[(<()>
+(((({<>}<{<{<>}{[]{[]{}
+[[<[([]))<([[{}[[()]]]
+[{[{({}]{}}([{[{{{}}([]
+{<[[]]>}<{[{[{[]{()[[[]
+[<(<(<(<{}))><([]([]()
+<{([([[(<>()){}]>(<<{{
+<{([{{}}[<[[[<>{}]]]>[]]
+"
+        .as_bytes();
+
+        assert_eq!(
+            ("26397".to_owned(), "288957".to_owned()),
+            stream(&mut input).unwrap()
+        );
+    }
 }
@@ -1,40 +1,103 @@
+use crate::parse::{finish, many_to_end, rectangular, tag, IResult};
+use crate::solver::{parse_param, unknown_param, Solver};
 use crate::Error;
-use nom::bytes::complete::tag;
 use nom::character::complete::digit1;
-use nom::multi::many0;
-use nom::IResult;
-use std::fs::read_to_string;
 
-pub fn calculate() -> Result<(String, String), Error> {
-    let input = parse_input(&read_to_string("input/day11")?)?;
+pub struct Day11 {
+    /// Steps to simulate in part 1.
+    pub steps: u32,
+    /// Width and height of the octopus grid.
+    pub size: usize,
+}
 
-    Ok((part1(&input)?, part2(&input)?))
+impl Default for Day11 {
+    fn default() -> Self {
+        Day11 {
+            steps: 100,
+            size: 10,
+        }
+    }
+}
+
+impl Solver for Day11 {
+    type Input = Vec<Vec<u8>>;
+
+    fn day(&self) -> u8 {
+        11
+    }
+
+    fn name(&self) -> &'static str {
+        "Dumbo Octopus"
+    }
+
+    fn parse(&self, input: &str) -> Result<Self::Input, Error> {
+        parse_input_sized(input, self.size)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Result<String, Error> {
+        flashes_after(input, self.steps)
+    }
+
+    fn part2(&self, input: &Self::Input) -> Result<String, Error> {
+        part2(input)
+    }
+
+    fn params(&self) -> &'static [&'static str] {
+        &["steps", "size"]
+    }
+
+    fn set_param(&mut self, key: &str, value: &str) -> Result<(), Error> {
+        match key {
+            "steps" => self.steps = parse_param(key, value, 0..=MAX_PART1_STEPS)?,
+            "size" => self.size = parse_param(key, value, 1..=MAX_SIZE)?,
+            _ => return Err(unknown_param(self.day(), key, self.params())),
+        }
+
+        Ok(())
+    }
 }
 
 fn my_u32(input: &str) -> IResult<&str, Vec<u8>> {
     let (rest, data) = digit1(input)?;
     let (rest, _) = tag("\n")(rest)?;
 
-    Ok((
-        rest,
-        data.chars()
-            .map(|d| d.to_digit(10).unwrap() as u8)
-            .collect(),
-    ))
+    Ok((rest, data.bytes().map(|d| d - b'0').collect()))
 }
 
 fn multi(i: &str) -> IResult<&str, Vec<Vec<u8>>> {
-    many0(my_u32)(i)
+    many_to_end(my_u32)(i)
+}
+
+pub fn parse_input(input: &str) -> Result<Vec<Vec<u8>>, Error> {
+    parse_input_sized(input, 10)
 }
 
-fn parse_input(input: &str) -> Result<Vec<Vec<u8>>, Error> {
-    let (_, data) = multi(input)?;
+/// Parses a grid of octopuses that has to be `size` by `size`.
+pub fn parse_input_sized(input: &str, size: usize) -> Result<Vec<Vec<u8>>, Error> {
+    let grid = rectangular(finish(input, multi)?)?;
+    if grid.len() != size || grid[0].len() != size {
+        return Err(Error::InvalidState(format!(
+            "the octopus grid is {} by {}, expected {} by {}",
+            grid.len(),
+            grid[0].len(),
+            size,
+            size
+        )));
+    }
 
-    Ok(data)
+    Ok(grid)
 }
 
-fn step(state: &mut [Vec<u8>]) -> u32 {
-    let mut flashes = [[false; 10]; 10];
+/// Steps to simulate before giving up on the octopuses synchronizing.
+const MAX_STEPS: u32 = 100_000;
+/// Most steps part 1 can be asked to simulate.
+const MAX_PART1_STEPS: u32 = 1000;
+/// Largest side of the grid.
+pub const MAX_SIZE: usize = 1000;
+
+fn step(state: &mut [Vec<u8>]) -> usize {
+    let size = state.len();
+    let mut flashes = vec![vec![false; size]; size];
 
     for r in state.iter_mut() {
         for c in r.iter_mut() {
@@ -46,36 +109,17 @@ fn step(state: &mut [Vec<u8>]) -> u32 {
 
     while flashed {
         flashed = false;
-        for x in 0..10 {
-            for y in 0..10 {
+        for x in 0..size {
+            for y in 0..size {
                 if state[x][y] > 9 && !flashes[x][y] {
                     flashed = true;
                     flashes[x][y] = true;
 
-                    if x > 0 && y > 0 {
-                        state[x - 1][y - 1] += 1;
-                    }
-                    if x > 0 {
-                        state[x - 1][y] += 1;
-                    }
-                    if x > 0 && y < 9 {
-                        state[x - 1][y + 1] += 1;
-                    }
-                    if y > 0 {
-                        state[x][y - 1] += 1;
-                    }
-                    state[x][y] += 1;
-                    if y < 9 {
-                        state[x][y + 1] += 1;
-                    }
-                    if x < 9 && y > 0 {
-                        state[x + 1][y - 1] += 1;
-                    }
-                    if x < 9 {
-                        state[x + 1][y] += 1;
-                    }
-                    if x < 9 && y < 9 {
-                        state[x + 1][y + 1] += 1;
+                    let columns = y.saturating_sub(1)..(y + 2).min(size);
+                    for row in &mut state[x.saturating_sub(1)..(x + 2).min(size)] {
+                        for c in &mut row[columns.clone()] {
+                            *c += 1;
+                        }
                     }
                 }
             }
@@ -94,36 +138,48 @@ fn step(state: &mut [Vec<u8>]) -> u32 {
     num_flashes
 }
 
-fn part1(input: &[Vec<u8>]) -> Result<String, Error> {
+/// Total number of flashes over `steps` steps.
+pub fn flashes_after(input: &[Vec<u8>], steps: u32) -> Result<String, Error> {
     let mut state: Vec<Vec<u8>> = input.to_vec();
 
-    let mut flashes = 0;
-    for _ in 0..100 {
-        flashes += step(&mut state);
+    let mut flashes = 0u64;
+    for _ in 0..steps {
+        flashes += step(&mut state) as u64;
     }
 
     Ok(format!("{}", flashes))
 }
 
-fn part2(input: &[Vec<u8>]) -> Result<String, Error> {
+pub fn part1(input: &[Vec<u8>]) -> Result<String, Error> {
+    flashes_after(input, 100)
+}
+
+/// The first step where all octopuses flash at once, if it happens within `max_steps`.
+pub fn first_sync(input: &[Vec<u8>], max_steps: u32) -> Option<u32> {
     let mut state: Vec<Vec<u8>> = input.to_vec();
 
-    let mut i = 0;
-    loop {
-        i += 1;
-        let flashes = step(&mut state);
-        if flashes == 100 {
-            return Ok(format!("{}", i));
-        }
+    let all = state.len() * state.len();
+
+    (1..=max_steps).find(|_| step(&mut state) == all)
+}
+
+pub fn part2(input: &[Vec<u8>]) -> Result<String, Error> {
+    match first_sync(input, MAX_STEPS) {
+        Some(i) => Ok(format!("{}", i)),
+        None => Err(Error::NoSolution("the octopuses never all flash at once")),
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::day11::flashes_after;
     use crate::day11::parse_input;
+    use crate::day11::parse_input_sized;
     use crate::day11::part1;
     use crate::day11::part2;
     use crate::day11::step;
+    use crate::day11::Day11;
+    use crate::solver::Solver;
 
     #[test]
     fn test_step() {
@@ -204,4 +260,27 @@ mod tests {
     pub fn test_part2() {
         assert_eq!("195", part2(&test_input()).unwrap());
     }
+
+    #[test]
+    pub fn test_flashes_after() {
+        assert_eq!("204", flashes_after(&test_input(), 10).unwrap());
+    }
+
+    #[test]
+    pub fn test_parse_sized() {
+        assert_eq!(
+            vec![vec![1, 2], vec![3, 4]],
+            parse_input_sized("12\n34\n", 2).unwrap()
+        );
+        assert!(parse_input_sized("12\n34\n", 10).is_err());
+    }
+
+    #[test]
+    pub fn test_set_steps() {
+        let mut day11 = Day11::default();
+
+        assert!(day11.set_param("steps", "1000").is_ok());
+        assert!(day11.set_param("steps", "1001").is_err());
+        assert_eq!(1000, day11.steps);
+    }
 }
@@ -1,18 +1,36 @@
+use crate::parse::{finish, many_to_end, tag, IResult};
+use crate::solver::Solver;
 use crate::Error;
-use nom::bytes::complete::tag;
 use nom::character::complete::alpha1;
-use nom::multi::many0;
-use nom::IResult;
-use std::fs::read_to_string;
 
-pub fn calculate() -> Result<(String, String), Error> {
-    let input = parse_input(&read_to_string("input/day12")?)?;
+pub struct Day12;
 
-    Ok((part1(&input)?, part2(&input)?))
+impl Solver for Day12 {
+    type Input = Graph;
+
+    fn day(&self) -> u8 {
+        12
+    }
+
+    fn name(&self) -> &'static str {
+        "Passage Pathing"
+    }
+
+    fn parse(&self, input: &str) -> Result<Self::Input, Error> {
+        parse_input(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Result<String, Error> {
+        part1(input)
+    }
+
+    fn part2(&self, input: &Self::Input) -> Result<String, Error> {
+        part2(input)
+    }
 }
 
 #[derive(PartialEq, Debug)]
-enum Room {
+pub enum Room {
     Start,
     Large(String),
     Small(String),
@@ -56,9 +74,9 @@ fn get_or_insert(vec: &mut Vec<Room>, room: Room) -> usize {
 }
 
 #[derive(PartialEq, Debug)]
-struct Graph {
-    nodes: Vec<Room>,
-    edges: Vec<(usize, usize)>,
+pub struct Graph {
+    pub nodes: Vec<Room>,
+    pub edges: Vec<(usize, usize)>,
 }
 
 impl Graph {
@@ -76,6 +94,20 @@ impl Graph {
         Graph { nodes, edges }
     }
 
+    /// Two connected large caves would let a path bounce between them forever.
+    fn check_large_caves(&self) -> Result<(), Error> {
+        for (a, b) in &self.edges {
+            if let (Room::Large(a), Room::Large(b)) = (&self.nodes[*a], &self.nodes[*b]) {
+                return Err(Error::InvalidState(format!(
+                    "large caves {} and {} are connected, there are endless paths",
+                    a, b
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
     fn neighbours(&self, pos: usize) -> Vec<usize> {
         let mut ret = vec![];
         for (a, b) in &self.edges {
@@ -196,24 +228,32 @@ impl Graph {
         paths
     }
 
-    fn paths(&self, from: &Room) -> Vec<Vec<usize>> {
-        let f_idx = self.nodes.iter().position(|r| r == from).unwrap();
+    fn paths(&self, from: &Room) -> Result<Vec<Vec<usize>>, Error> {
+        let f_idx = self
+            .nodes
+            .iter()
+            .position(|r| r == from)
+            .ok_or_else(|| Error::InvalidState(format!("there is no {:?} cave", from)))?;
 
         let mut paths = vec![];
 
         paths.extend_from_slice(&self.next_step(f_idx, vec![]));
 
-        paths
+        Ok(paths)
     }
 
-    fn paths_with_repeat(&self, from: &Room) -> Vec<Vec<usize>> {
-        let f_idx = self.nodes.iter().position(|r| r == from).unwrap();
+    fn paths_with_repeat(&self, from: &Room) -> Result<Vec<Vec<usize>>, Error> {
+        let f_idx = self
+            .nodes
+            .iter()
+            .position(|r| r == from)
+            .ok_or_else(|| Error::InvalidState(format!("there is no {:?} cave", from)))?;
 
         let mut paths = vec![];
 
         paths.extend_from_slice(&self.next_step_with_repeat(f_idx, false, vec![]));
 
-        paths
+        Ok(paths)
     }
 }
 
@@ -227,25 +267,26 @@ fn room_pair(input: &str) -> IResult<&str, (Room, Room)> {
 }
 
 fn multi(i: &str) -> IResult<&str, Graph> {
-    let (rest, corridors) = many0(room_pair)(i)?;
+    let (rest, corridors) = many_to_end(room_pair)(i)?;
 
     Ok((rest, Graph::from_edges(corridors)))
 }
 
-fn parse_input(input: &str) -> Result<Graph, Error> {
-    let (_, data) = multi(input)?;
+pub fn parse_input(input: &str) -> Result<Graph, Error> {
+    let graph = finish(input, multi)?;
+    graph.check_large_caves()?;
 
-    Ok(data)
+    Ok(graph)
 }
 
-fn part1(input: &Graph) -> Result<String, Error> {
-    let paths = input.paths(&Room::Start);
+pub fn part1(input: &Graph) -> Result<String, Error> {
+    let paths = input.paths(&Room::Start)?;
 
     Ok(format!("{}", paths.len()))
 }
 
-fn part2(input: &Graph) -> Result<String, Error> {
-    let paths = input.paths_with_repeat(&Room::Start);
+pub fn part2(input: &Graph) -> Result<String, Error> {
+    let paths = input.paths_with_repeat(&Room::Start)?;
 
     Ok(format!("{}", paths.len()))
 }
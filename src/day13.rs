@@ -1,30 +1,49 @@
+use crate::parse::{finish, many_to_end, tag, IResult};
+use crate::solver::Solver;
 use crate::Error;
 use nom::branch::alt;
-use nom::bytes::complete::tag;
 use nom::character::complete::digit1;
 use nom::combinator::{map_res, recognize};
 use nom::multi::many0;
-use nom::IResult;
 use std::cmp::Ordering;
 use std::collections::HashSet;
-use std::fs::read_to_string;
 
-pub fn calculate() -> Result<(String, String), Error> {
-    let input = parse_input(&read_to_string("input/day13")?)?;
+pub struct Day13;
 
-    Ok((part1(&input)?, part2(&input)?))
+impl Solver for Day13 {
+    type Input = Instructions;
+
+    fn day(&self) -> u8 {
+        13
+    }
+
+    fn name(&self) -> &'static str {
+        "Transparent Origami"
+    }
+
+    fn parse(&self, input: &str) -> Result<Self::Input, Error> {
+        parse_input(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Result<String, Error> {
+        part1(input)
+    }
+
+    fn part2(&self, input: &Self::Input) -> Result<String, Error> {
+        part2(input)
+    }
 }
 
 #[derive(PartialEq, Debug)]
-enum Direction {
+pub enum Direction {
     X,
     Y,
 }
 
 #[derive(PartialEq, Debug)]
-struct Instructions {
-    points: Vec<(u32, u32)>,
-    folds: Vec<(Direction, u32)>,
+pub struct Instructions {
+    pub points: Vec<(u32, u32)>,
+    pub folds: Vec<(Direction, u32)>,
 }
 
 fn point(input: &str) -> IResult<&str, (u32, u32)> {
@@ -57,24 +76,43 @@ fn fold(input: &str) -> IResult<&str, (Direction, u32)> {
 }
 
 fn multi_folds(i: &str) -> IResult<&str, Vec<(Direction, u32)>> {
-    many0(fold)(i)
+    many_to_end(fold)(i)
 }
 
-fn parse_input(input: &str) -> Result<Instructions, Error> {
+fn instructions(input: &str) -> IResult<&str, Instructions> {
     let (rest, points) = multi_points(input)?;
     let (rest, _) = tag("\n")(rest)?;
-    let (_, folds) = multi_folds(rest)?;
+    let (rest, folds) = multi_folds(rest)?;
+
+    Ok((rest, Instructions { points, folds }))
+}
+
+pub fn parse_input(input: &str) -> Result<Instructions, Error> {
+    finish(input, instructions)
+}
 
-    Ok(Instructions { points, folds })
+/// Where a coordinate past the fold line ends up, `None` if it lands outside the paper.
+fn mirror(v: u32, amount: u32) -> Option<u32> {
+    amount.checked_mul(2)?.checked_sub(v)
 }
 
-fn fold_points(points: &[(u32, u32)], dir: &Direction, amount: &u32) -> Vec<(u32, u32)> {
+fn fold_points(
+    points: &[(u32, u32)],
+    dir: &Direction,
+    amount: &u32,
+) -> Result<Vec<(u32, u32)>, Error> {
+    let off_paper = |x, y| {
+        Error::InvalidState(format!(
+            "dot {},{} folds past the edge of the paper along {:?}={}",
+            x, y, dir, amount
+        ))
+    };
     let mut new_points = HashSet::new();
     for (x, y) in points {
         if *dir == Direction::X {
             match x.cmp(amount) {
                 Ordering::Greater => {
-                    new_points.insert((amount * 2 - *x, *y));
+                    new_points.insert((mirror(*x, *amount).ok_or_else(|| off_paper(x, y))?, *y));
                 }
                 Ordering::Less => {
                     new_points.insert((*x, *y));
@@ -84,7 +122,7 @@ fn fold_points(points: &[(u32, u32)], dir: &Direction, amount: &u32) -> Vec<(u32
         } else {
             match y.cmp(amount) {
                 Ordering::Greater => {
-                    new_points.insert((*x, amount * 2 - *y));
+                    new_points.insert((*x, mirror(*y, *amount).ok_or_else(|| off_paper(x, y))?));
                 }
                 Ordering::Less => {
                     new_points.insert((*x, *y));
@@ -93,43 +131,56 @@ fn fold_points(points: &[(u32, u32)], dir: &Direction, amount: &u32) -> Vec<(u32
             }
         }
     }
-    new_points.into_iter().collect()
+    Ok(new_points.into_iter().collect())
 }
 
-fn plot_grid(input: &[(u32, u32)]) -> String {
-    let max_x = input.iter().map(|(x, _)| x).max().unwrap() + 2;
-    let max_y = input.iter().map(|(_, y)| y).max().unwrap() + 1;
+/// Largest paper, in cells, that will be plotted.
+const MAX_PLOT_AREA: usize = 10_000_000;
+
+fn plot_grid(input: &[(u32, u32)]) -> Result<String, Error> {
+    let max_x = match input.iter().map(|(x, _)| x).max() {
+        Some(x) => *x as usize + 2,
+        None => return Ok(String::new()),
+    };
+    let max_y = input.iter().map(|(_, y)| *y as usize).max().unwrap_or(0) + 1;
+    if max_x.saturating_mul(max_y) > MAX_PLOT_AREA {
+        return Err(Error::InvalidState(format!(
+            "the folded paper is {} by {}, too large to plot",
+            max_x - 1,
+            max_y
+        )));
+    }
 
-    let mut chars = vec!['.'; (max_x * max_y) as usize];
+    let mut chars = vec!['.'; max_x * max_y];
 
     for (x, y) in input {
-        chars[(x + max_x * (y)) as usize] = '#';
+        chars[*x as usize + max_x * *y as usize] = '#';
     }
 
     for i in 0..max_y {
-        chars[((i + 1) * max_x - 1) as usize] = '\n';
+        chars[(i + 1) * max_x - 1] = '\n';
     }
 
-    chars.into_iter().collect()
+    Ok(chars.into_iter().collect())
 }
 
-fn part1(input: &Instructions) -> Result<String, Error> {
-    if let Some((dir, amount)) = input.folds.get(0) {
-        let new_points = fold_points(&input.points, dir, amount);
+pub fn part1(input: &Instructions) -> Result<String, Error> {
+    if let Some((dir, amount)) = input.folds.first() {
+        let new_points = fold_points(&input.points, dir, amount)?;
         Ok(format!("{}", new_points.len()))
     } else {
-        Err(Error::Generic("no folds in input"))
+        Err(Error::InvalidState("no folds in input".to_owned()))
     }
 }
 
-fn part2(input: &Instructions) -> Result<String, Error> {
+pub fn part2(input: &Instructions) -> Result<String, Error> {
     let mut points = input.points.clone();
     for (dir, amount) in &input.folds {
-        let new_points = fold_points(&points, dir, amount);
+        let new_points = fold_points(&points, dir, amount)?;
         points.resize(0, (0, 0));
         points.extend_from_slice(&new_points);
     }
-    let grid = plot_grid(&points);
+    let grid = plot_grid(&points)?;
 
     Ok(format!("\n{}", grid))
 }
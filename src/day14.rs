@@ -1,22 +1,75 @@
+use crate::parse::{finish, many_to_end, tag, IResult};
+use crate::solver::{parse_param, unknown_param, Solver};
 use crate::Error;
-use nom::bytes::complete::tag;
 use nom::character::complete::alpha1;
 use nom::character::complete::anychar;
-use nom::multi::many0;
-use nom::IResult;
 use std::collections::HashMap;
-use std::fs::read_to_string;
 
-pub fn calculate() -> Result<(String, String), Error> {
-    let input = parse_input(&read_to_string("input/day14")?)?;
+pub struct Day14 {
+    /// Insertion steps in part 1.
+    pub part1_steps: usize,
+    /// Insertion steps in part 2.
+    pub part2_steps: usize,
+}
+
+impl Default for Day14 {
+    fn default() -> Self {
+        Day14 {
+            part1_steps: 10,
+            part2_steps: 40,
+        }
+    }
+}
+
+/// Most insertion steps, the polymer overflows 64 bits long before this anyway.
+const MAX_STEPS: usize = 1000;
+
+impl Solver for Day14 {
+    type Input = State;
+
+    fn day(&self) -> u8 {
+        14
+    }
+
+    fn name(&self) -> &'static str {
+        "Extended Polymerization"
+    }
+
+    fn parse(&self, input: &str) -> Result<Self::Input, Error> {
+        parse_input(input)
+    }
 
-    Ok((part1(&input)?, part2(&input)?))
+    fn part1(&self, input: &Self::Input) -> Result<String, Error> {
+        polymer_after(input, self.part1_steps)
+    }
+
+    fn part2(&self, input: &Self::Input) -> Result<String, Error> {
+        polymer_after(input, self.part2_steps)
+    }
+
+    fn params(&self) -> &'static [&'static str] {
+        &["steps", "part1_steps", "part2_steps"]
+    }
+
+    fn set_param(&mut self, key: &str, value: &str) -> Result<(), Error> {
+        match key {
+            "steps" => {
+                self.part1_steps = parse_param(key, value, 0..=MAX_STEPS)?;
+                self.part2_steps = self.part1_steps;
+            }
+            "part1_steps" => self.part1_steps = parse_param(key, value, 0..=MAX_STEPS)?,
+            "part2_steps" => self.part2_steps = parse_param(key, value, 0..=MAX_STEPS)?,
+            _ => return Err(unknown_param(self.day(), key, self.params())),
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(PartialEq, Debug)]
-struct State {
-    start: Vec<char>,
-    templates: HashMap<(char, char), char>,
+pub struct State {
+    pub start: Vec<char>,
+    pub templates: HashMap<(char, char), char>,
 }
 
 #[derive(PartialEq, Debug)]
@@ -42,55 +95,68 @@ fn template(input: &str) -> IResult<&str, Template> {
 }
 
 fn multi_template(i: &str) -> IResult<&str, Vec<Template>> {
-    many0(template)(i)
+    many_to_end(template)(i)
 }
 
-fn parse_input(input: &str) -> Result<State, Error> {
+fn state(input: &str) -> IResult<&str, State> {
     let (rest, start_state) = alpha1(input)?;
     let (rest, _) = tag("\n\n")(rest)?;
-    let (_, templates) = multi_template(rest)?;
+    let (rest, templates) = multi_template(rest)?;
 
     let mut map = HashMap::new();
     for t in templates {
         map.insert(t.source, t.result);
     }
-    Ok(State {
-        start: start_state.chars().collect(),
-        templates: map,
-    })
+    Ok((
+        rest,
+        State {
+            start: start_state.chars().collect(),
+            templates: map,
+        },
+    ))
+}
+
+pub fn parse_input(input: &str) -> Result<State, Error> {
+    finish(input, state)
+}
+
+fn too_long() -> Error {
+    Error::InvalidState("the polymer is too long to count in 64 bits".to_owned())
 }
 
 fn expand_state(
     state: &HashMap<(char, char), u64>,
     templates: &HashMap<(char, char), char>,
-) -> HashMap<(char, char), u64> {
+) -> Result<HashMap<(char, char), u64>, Error> {
     let mut new_state = HashMap::new();
 
     for (k, v) in state {
-        let to_insert = templates.get(k).unwrap();
+        let to_insert = templates.get(k).ok_or_else(|| {
+            Error::InvalidState(format!("no insertion rule for the pair {}{}", k.0, k.1))
+        })?;
 
-        let counter = new_state.entry((k.0, *to_insert)).or_insert(0);
-        *counter += v;
-        let counter = new_state.entry((*to_insert, k.1)).or_insert(0);
-        *counter += v;
+        let counter = new_state.entry((k.0, *to_insert)).or_insert(0u64);
+        *counter = counter.checked_add(*v).ok_or_else(too_long)?;
+        let counter = new_state.entry((*to_insert, k.1)).or_insert(0u64);
+        *counter = counter.checked_add(*v).ok_or_else(too_long)?;
     }
 
-    new_state
+    Ok(new_state)
 }
 
-fn calc(state: &HashMap<(char, char), u64>, start: (char, char)) -> u64 {
+fn calc(state: &HashMap<(char, char), u64>, start: (char, char)) -> Result<u64, Error> {
     let mut map = HashMap::new();
 
     for (k, v) in state {
-        let counter = map.entry(k.0).or_insert(0);
-        *counter += v;
-        let counter = map.entry(k.1).or_insert(0);
-        *counter += v;
+        let counter = map.entry(k.0).or_insert(0u64);
+        *counter = counter.checked_add(*v).ok_or_else(too_long)?;
+        let counter = map.entry(k.1).or_insert(0u64);
+        *counter = counter.checked_add(*v).ok_or_else(too_long)?;
     }
     let counter = map.entry(start.0).or_insert(0);
-    *counter += 1;
+    *counter = counter.checked_add(1).ok_or_else(too_long)?;
     let counter = map.entry(start.1).or_insert(0);
-    *counter += 1;
+    *counter = counter.checked_add(1).ok_or_else(too_long)?;
 
     let mut max = 0;
     let mut min = u64::MAX;
@@ -104,48 +170,48 @@ fn calc(state: &HashMap<(char, char), u64>, start: (char, char)) -> u64 {
         }
     }
 
-    (max - min) / 2
+    Ok((max - min) / 2)
 }
 
 fn precalc(input: &State) -> HashMap<(char, char), u64> {
     let mut state = HashMap::new();
-    for i in 0..(input.start.len() - 1) {
-        let counter = state
-            .entry((input.start[i], input.start[i + 1]))
-            .or_insert(0);
+    for pair in input.start.windows(2) {
+        let counter = state.entry((pair[0], pair[1])).or_insert(0);
         *counter += 1;
     }
     state
 }
 
-fn part1(input: &State) -> Result<String, Error> {
-    let mut state = precalc(input);
-    for _ in 0..10 {
-        state = expand_state(&state, &input.templates);
+fn ends(input: &State) -> Result<(char, char), Error> {
+    match (input.start.first(), input.start.last()) {
+        (Some(first), Some(last)) => Ok((*first, *last)),
+        _ => Err(Error::InvalidState(
+            "the polymer template is empty".to_owned(),
+        )),
     }
-
-    Ok(format!(
-        "{}",
-        calc(
-            &state,
-            (*input.start.get(0).unwrap(), *input.start.last().unwrap())
-        )
-    ))
 }
 
-fn part2(input: &State) -> Result<String, Error> {
+/// Difference between the most and least common element after `steps` insertion steps.
+pub fn polymer_after(input: &State, steps: usize) -> Result<String, Error> {
     let mut state = precalc(input);
-    for _ in 0..40 {
-        state = expand_state(&state, &input.templates);
+    for _ in 0..steps {
+        let next = expand_state(&state, &input.templates)?;
+        // Only a template without pairs stays the same, and then it always will.
+        if next == state {
+            break;
+        }
+        state = next;
     }
 
-    Ok(format!(
-        "{}",
-        calc(
-            &state,
-            (*input.start.get(0).unwrap(), *input.start.last().unwrap())
-        )
-    ))
+    Ok(format!("{}", calc(&state, ends(input)?)?))
+}
+
+pub fn part1(input: &State) -> Result<String, Error> {
+    polymer_after(input, 10)
+}
+
+pub fn part2(input: &State) -> Result<String, Error> {
+    polymer_after(input, 40)
 }
 
 #[cfg(test)]
@@ -153,8 +219,11 @@ mod tests {
     use crate::day14::parse_input;
     use crate::day14::part1;
     use crate::day14::part2;
+    use crate::day14::polymer_after;
     use crate::day14::State;
     use crate::day14::Template;
+    use crate::day14::{Day14, MAX_STEPS};
+    use crate::solver::Solver;
     use std::collections::HashMap;
 
     fn test_input() -> State {
@@ -271,4 +340,29 @@ CN -> C
     pub fn test_part2() {
         assert_eq!("2188189693529", part2(&test_input()).unwrap());
     }
+
+    #[test]
+    pub fn test_polymer_after() {
+        assert_eq!("1", polymer_after(&test_input(), 0).unwrap());
+        assert!(polymer_after(&test_input(), 100).is_err());
+    }
+
+    #[test]
+    pub fn test_single_element_template() {
+        let input = parse_input("N\n\nNN -> C\n").unwrap();
+
+        assert_eq!("0", polymer_after(&input, usize::MAX).unwrap());
+    }
+
+    #[test]
+    pub fn test_steps_limit() {
+        let mut day = Day14::default();
+
+        assert!(day.set_param("steps", &MAX_STEPS.to_string()).is_ok());
+        assert!(day.set_param("steps", &usize::MAX.to_string()).is_err());
+        assert!(day
+            .set_param("part2_steps", &(MAX_STEPS + 1).to_string())
+            .is_err());
+        assert_eq!(MAX_STEPS, day.part2_steps);
+    }
 }
@@ -1,41 +1,74 @@
+use crate::parse::{finish, many_to_end, rectangular, tag, IResult};
+use crate::solver::{parse_param, unknown_param, Solver};
 use crate::Error;
-use nom::bytes::complete::tag;
 use nom::character::complete::digit1;
-use nom::multi::many0;
-use nom::IResult;
 use std::cmp::Ordering;
 use std::collections::hash_map::Entry::{Occupied, Vacant};
 use std::collections::BinaryHeap;
 use std::collections::HashMap;
-use std::fs::read_to_string;
 use std::hash::Hash;
 
-pub fn calculate() -> Result<(String, String), Error> {
-    let input = parse_input(&read_to_string("input/day15")?)?;
+pub struct Day15 {
+    /// How many times the map is repeated in each direction in part 2.
+    pub tiles: usize,
+}
 
-    Ok((part1(&input)?, part2(&input)?))
+impl Default for Day15 {
+    fn default() -> Self {
+        Day15 { tiles: 5 }
+    }
+}
+
+impl Solver for Day15 {
+    type Input = Vec<Vec<u8>>;
+
+    fn day(&self) -> u8 {
+        15
+    }
+
+    fn name(&self) -> &'static str {
+        "Chiton"
+    }
+
+    fn parse(&self, input: &str) -> Result<Self::Input, Error> {
+        parse_input(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Result<String, Error> {
+        part1(input)
+    }
+
+    fn part2(&self, input: &Self::Input) -> Result<String, Error> {
+        tiled_risk(input, self.tiles)
+    }
+
+    fn params(&self) -> &'static [&'static str] {
+        &["tiles"]
+    }
+
+    fn set_param(&mut self, key: &str, value: &str) -> Result<(), Error> {
+        match key {
+            "tiles" => self.tiles = parse_param(key, value, 1..=1000)?,
+            _ => return Err(unknown_param(self.day(), key, self.params())),
+        }
+
+        Ok(())
+    }
 }
 
 fn my_u32(input: &str) -> IResult<&str, Vec<u8>> {
     let (rest, data) = digit1(input)?;
     let (rest, _) = tag("\n")(rest)?;
 
-    Ok((
-        rest,
-        data.chars()
-            .map(|d| d.to_digit(10).unwrap() as u8)
-            .collect(),
-    ))
+    Ok((rest, data.bytes().map(|d| d - b'0').collect()))
 }
 
 fn multi(i: &str) -> IResult<&str, Vec<Vec<u8>>> {
-    many0(my_u32)(i)
+    many_to_end(my_u32)(i)
 }
 
-fn parse_input(input: &str) -> Result<Vec<Vec<u8>>, Error> {
-    let (_, data) = multi(input)?;
-
-    Ok(data)
+pub fn parse_input(input: &str) -> Result<Vec<Vec<u8>>, Error> {
+    rectangular(finish(input, multi)?)
 }
 
 fn neighbours(
@@ -96,7 +129,7 @@ impl<K: Ord, P> Ord for SmallestCostHolder<K, P> {
     }
 }
 
-fn a_star(start: (usize, usize), goal: (usize, usize), h: &[Vec<u8>]) -> Vec<(usize, usize)> {
+pub fn a_star(start: (usize, usize), goal: (usize, usize), h: &[Vec<u8>]) -> Vec<(usize, usize)> {
     let heuristic = |(_, _)| 0u32;
 
     let mut to_see = BinaryHeap::new();
@@ -152,11 +185,15 @@ fn a_star(start: (usize, usize), goal: (usize, usize), h: &[Vec<u8>]) -> Vec<(us
     }
 
     // Open set is empty but goal was never reached
-    return vec![];
+    vec![]
 }
 
-fn part1(input: &[Vec<u8>]) -> Result<String, Error> {
-    let route = a_star((0, 0), (input.len() - 1, input[0].len() - 1), input);
+fn lowest_risk(input: &[Vec<u8>]) -> Result<String, Error> {
+    let width = input.first().map_or(0, Vec::len);
+    if width == 0 {
+        return Err(Error::InvalidState("no risk levels in input".to_owned()));
+    }
+    let route = a_star((0, 0), (input.len() - 1, width - 1), input);
 
     Ok(format!(
         "{}",
@@ -168,18 +205,27 @@ fn part1(input: &[Vec<u8>]) -> Result<String, Error> {
     ))
 }
 
-fn five_times_map(input: &[Vec<u8>]) -> Vec<Vec<u8>> {
+pub fn part1(input: &[Vec<u8>]) -> Result<String, Error> {
+    lowest_risk(input)
+}
+
+/// Largest map, in cells, that part 2 will search.
+const MAX_CELLS: usize = 10_000_000;
+
+/// Repeats the map `tiles` times in each direction, every repetition to the right or
+/// down increases the risk by one, wrapping around from 9 to 1.
+pub fn tile_map(input: &[Vec<u8>], tiles: usize) -> Vec<Vec<u8>> {
     let x_max = input.len();
-    let y_max = input[0].len();
-    let mut new = vec![vec![0u8; y_max * 5]; x_max * 5];
+    let y_max = input.first().map_or(0, Vec::len);
+    let mut new = vec![vec![0u8; y_max * tiles]; x_max * tiles];
 
-    for x in 0..x_max * 5 {
-        for y in 0..y_max * 5 {
-            let new_val = input[x % x_max][y % y_max] + (x / x_max + y / y_max) as u8;
+    for x in 0..x_max * tiles {
+        for y in 0..y_max * tiles {
+            let new_val = input[x % x_max][y % y_max] as usize + x / x_max + y / y_max;
             if new_val > 9 {
-                new[x][y] = (new_val % 10) + 1;
+                new[x][y] = ((new_val - 1) % 9 + 1) as u8;
             } else {
-                new[x][y] = new_val;
+                new[x][y] = new_val as u8;
             }
         }
     }
@@ -187,18 +233,30 @@ fn five_times_map(input: &[Vec<u8>]) -> Vec<Vec<u8>> {
     new
 }
 
-fn part2(input: &[Vec<u8>]) -> Result<String, Error> {
-    let input = five_times_map(input);
-    let route = a_star((0, 0), (input.len() - 1, input[0].len() - 1), &input);
+pub fn five_times_map(input: &[Vec<u8>]) -> Vec<Vec<u8>> {
+    tile_map(input, 5)
+}
 
-    Ok(format!(
-        "{}",
-        route
-            .iter()
-            .skip(1)
-            .map(|(x, y)| input[*x][*y] as u32)
-            .sum::<u32>()
-    ))
+/// Lowest total risk through the map repeated `tiles` times in each direction.
+pub fn tiled_risk(input: &[Vec<u8>], tiles: usize) -> Result<String, Error> {
+    let width = input.first().map_or(0, Vec::len);
+    let cells = input
+        .len()
+        .saturating_mul(width)
+        .saturating_mul(tiles)
+        .saturating_mul(tiles);
+    if cells > MAX_CELLS {
+        return Err(Error::InvalidState(format!(
+            "the tiled map has {} cells, more than the {} that can be searched",
+            cells, MAX_CELLS
+        )));
+    }
+
+    lowest_risk(&tile_map(input, tiles))
+}
+
+pub fn part2(input: &[Vec<u8>]) -> Result<String, Error> {
+    tiled_risk(input, 5)
 }
 
 #[cfg(test)]
@@ -207,6 +265,8 @@ mod tests {
     use crate::day15::parse_input;
     use crate::day15::part1;
     use crate::day15::part2;
+    use crate::day15::tiled_risk;
+    use crate::Error;
 
     fn test_input() -> Vec<Vec<u8>> {
         vec![
@@ -461,4 +521,21 @@ mod tests {
     pub fn test_part2() {
         assert_eq!("315", part2(&test_input()).unwrap());
     }
+
+    #[test]
+    pub fn test_tiled_risk() {
+        assert_eq!("40", tiled_risk(&test_input(), 1).unwrap());
+        assert!(tiled_risk(&test_input(), 1000).is_err());
+    }
+
+    #[test]
+    pub fn test_empty_map() {
+        assert!(matches!(part1(&[]), Err(Error::InvalidState(_))));
+        assert!(matches!(part1(&[vec![]]), Err(Error::InvalidState(_))));
+        assert!(matches!(tiled_risk(&[], 5), Err(Error::InvalidState(_))));
+        assert!(matches!(
+            tiled_risk(&test_input(), 0),
+            Err(Error::InvalidState(_))
+        ));
+    }
 }
@@ -1,53 +1,94 @@
+use crate::parse::{self, finish, tag, Location};
+use crate::solver::Solver;
 use crate::Error;
 use nom::bits::complete::take;
-use nom::character::complete::alphanumeric1;
+use nom::character::complete::hex_digit1;
+use nom::error::ErrorKind;
 use nom::IResult;
-use std::fs::read_to_string;
-use std::num::ParseIntError;
 
-pub fn calculate() -> Result<(String, String), Error> {
-    let input = parse_input(&read_to_string("input/day16")?)?;
+pub struct Day16;
 
-    Ok((part1(&input)?, part2(&input)?))
+impl Solver for Day16 {
+    type Input = Vec<u8>;
+
+    fn day(&self) -> u8 {
+        16
+    }
+
+    fn name(&self) -> &'static str {
+        "Packet Decoder"
+    }
+
+    fn parse(&self, input: &str) -> Result<Self::Input, Error> {
+        parse_input(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Result<String, Error> {
+        part1(input)
+    }
+
+    fn part2(&self, input: &Self::Input) -> Result<String, Error> {
+        part2(input)
+    }
 }
 
-fn decode_hex(s: &str) -> Result<Vec<u8>, ParseIntError> {
-    (0..s.len())
-        .step_by(2)
-        .map(|i| u8::from_str_radix(&s[i..i + 2], 16))
+pub fn decode_hex(s: &str) -> Result<Vec<u8>, String> {
+    if !s.len().is_multiple_of(2) {
+        return Err(format!("odd number of hex digits ({})", s.len()));
+    }
+
+    s.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| format!("'{}' is not a hex byte", String::from_utf8_lossy(pair)))
+        })
         .collect()
 }
 
-fn parse_input(input: &str) -> Result<Vec<u8>, Error> {
-    let (_, data) = alphanumeric1(input)?;
+fn hex_line(input: &str) -> parse::IResult<&str, &str> {
+    let (rest, data) = hex_digit1(input)?;
+    let (rest, _) = tag("\n")(rest)?;
+
+    Ok((rest, data))
+}
 
-    Ok(decode_hex(data)?)
+pub fn parse_input(input: &str) -> Result<Vec<u8>, Error> {
+    decode_hex(finish(input, hex_line)?).map_err(Error::InvalidState)
 }
 
 #[derive(PartialEq, Debug)]
-struct Packet {
-    version: u8,
-    type_id: u8,
-    literal: Option<u64>,
-    length_type_id: Option<bool>,
-    length: Option<u16>,
-    sub_packets: Option<Vec<Packet>>,
+pub struct Packet {
+    pub version: u8,
+    pub type_id: u8,
+    pub literal: Option<u64>,
+    pub length_type_id: Option<bool>,
+    pub length: Option<u16>,
+    pub sub_packets: Option<Vec<Packet>>,
 }
 
-fn take_3_bits(i: (&[u8], usize)) -> IResult<(&[u8], usize), u8> {
+pub type BitInput<'a> = (&'a [u8], usize);
+
+/// How deep packets may be nested, deeper transmissions are rejected instead of
+/// overflowing the stack.
+pub const MAX_DEPTH: usize = 100;
+
+fn take_3_bits(i: BitInput) -> IResult<BitInput, u8> {
     take(3usize)(i)
 }
 
-fn parse_literal(i: (&[u8], usize)) -> IResult<(&[u8], usize), (Vec<u8>, usize)> {
+fn parse_literal(i: BitInput) -> IResult<BitInput, (Vec<u8>, usize)> {
     let mut last = false;
     let mut state = i;
     let mut buf = "".to_owned();
 
     let mut iter = 0;
     while !last {
-        let (state1, last_bit): ((&[u8], usize), u8) = take(1usize)(state)?;
+        let (state1, last_bit): (BitInput, u8) = take(1usize)(state)?;
         state = state1;
-        let (state1, val): ((&[u8], usize), u8) = take(4usize)(state)?;
+        let (state1, val): (BitInput, u8) = take(4usize)(state)?;
         state = state1;
 
         buf = format!("{}{:X?}", buf, val);
@@ -61,21 +102,35 @@ fn parse_literal(i: (&[u8], usize)) -> IResult<(&[u8], usize), (Vec<u8>, usize)>
         buf = format!("0{}", buf);
     }
 
-    Ok((state, (decode_hex(&buf).unwrap(), iter * 5)))
+    let literal = decode_hex(&buf)
+        .map_err(|_| nom::Err::Failure(nom::error::Error::new(i, ErrorKind::HexDigit)))?;
+
+    Ok((state, (literal, iter * 5)))
+}
+
+fn failure(input: BitInput, kind: ErrorKind) -> nom::Err<nom::error::Error<BitInput>> {
+    nom::Err::Failure(nom::error::Error::new(input, kind))
+}
+
+/// Parses a packet and its sub-packets, returning the packet and its length in bits.
+pub fn to_packets(input: BitInput) -> IResult<BitInput, (Packet, usize)> {
+    nested_packet(input, 0)
 }
 
-fn to_packets(input: (&[u8], usize)) -> IResult<(&[u8], usize), (Packet, usize)> {
+fn nested_packet(input: BitInput, depth: usize) -> IResult<BitInput, (Packet, usize)> {
+    if depth >= MAX_DEPTH {
+        return Err(failure(input, ErrorKind::Many1));
+    }
     let (rest, version) = take_3_bits(input)?;
     let (rest, type_id) = take_3_bits(rest)?;
 
     if type_id == 4 {
         let (rest, (literal, bit_length)) = parse_literal(rest)?;
 
-        let literal = literal
-            .into_iter()
-            .rev()
-            .enumerate()
-            .fold(0, |s, (i, v)| s + 256_u64.pow(i as u32) * (v as u64));
+        if literal.len() > 8 {
+            return Err(failure(rest, ErrorKind::TooLarge));
+        }
+        let literal = literal.into_iter().fold(0u64, |s, v| (s << 8) | v as u64);
         Ok((
             rest,
             (
@@ -91,26 +146,20 @@ fn to_packets(input: (&[u8], usize)) -> IResult<(&[u8], usize), (Packet, usize)>
             ),
         ))
     } else {
-        let (rest, length_type_id): ((&[u8], usize), u8) = take(1usize)(rest)?;
+        let (rest, length_type_id): (BitInput, u8) = take(1usize)(rest)?;
 
         if length_type_id > 0 {
-            let (rest, length): ((&[u8], usize), u16) = take(11usize)(rest)?;
+            let (rest, length): (BitInput, u16) = take(11usize)(rest)?;
 
             let mut sub_packets = vec![];
             let mut rest = rest;
             let mut bit_length = 18;
-            while {
-                let p = to_packets(rest);
-
-                if let Ok((r, p)) = p {
-                    sub_packets.push(p.0);
-                    bit_length += p.1;
-                    rest = r;
-                    sub_packets.len() < length as usize
-                } else {
-                    false
-                }
-            } {}
+            while sub_packets.len() < length as usize {
+                let (r, (p, l)) = nested_packet(rest, depth + 1)?;
+                sub_packets.push(p);
+                bit_length += l;
+                rest = r;
+            }
             Ok((
                 rest,
                 (
@@ -126,23 +175,20 @@ fn to_packets(input: (&[u8], usize)) -> IResult<(&[u8], usize), (Packet, usize)>
                 ),
             ))
         } else {
-            let (rest, length): ((&[u8], usize), u16) = take(15usize)(rest)?;
+            let (rest, length): (BitInput, u16) = take(15usize)(rest)?;
 
             let mut sub_packets = vec![];
             let mut rest = rest;
             let mut bit_length = 0;
-            while {
-                let p = to_packets(rest);
-
-                if let Ok((r, p)) = p {
-                    sub_packets.push(p.0);
-                    rest = r;
-                    bit_length += p.1;
-                    bit_length < length as usize
-                } else {
-                    false
-                }
-            } {}
+            while bit_length < length as usize {
+                let (r, (p, l)) = nested_packet(rest, depth + 1)?;
+                sub_packets.push(p);
+                rest = r;
+                bit_length += l;
+            }
+            if bit_length != length as usize {
+                return Err(failure(rest, ErrorKind::LengthValue));
+            }
             Ok((
                 rest,
                 (
@@ -161,95 +207,95 @@ fn to_packets(input: (&[u8], usize)) -> IResult<(&[u8], usize), (Packet, usize)>
     }
 }
 
-fn add_version(packet: &Packet) -> u64 {
+pub fn add_version(packet: &Packet) -> u64 {
     match &packet.sub_packets {
         None => packet.version.into(),
         Some(sb) => (packet.version as u64) + sb.iter().map(add_version).sum::<u64>(),
     }
 }
 
-fn eval(packet: &Packet) -> u64 {
+fn sub_packets(packet: &Packet) -> Result<&[Packet], Error> {
+    match &packet.sub_packets {
+        Some(sub_packets) if !sub_packets.is_empty() => Ok(sub_packets),
+        _ => Err(Error::InvalidState(format!(
+            "operator packet of type {} has no sub-packets",
+            packet.type_id
+        ))),
+    }
+}
+
+fn compare(packet: &Packet, cmp: fn(&u64, &u64) -> bool) -> Result<u64, Error> {
+    match sub_packets(packet)? {
+        [a, b] => Ok(cmp(&eval(a)?, &eval(b)?) as u64),
+        sub_packets => Err(Error::InvalidState(format!(
+            "comparison packet of type {} has {} sub-packets, expected 2",
+            packet.type_id,
+            sub_packets.len()
+        ))),
+    }
+}
+
+pub fn eval(packet: &Packet) -> Result<u64, Error> {
+    let overflow = || Error::InvalidState("packet value doesn't fit in 64 bits".to_owned());
+
     match packet.type_id {
-        0 => packet
-            .sub_packets
-            .as_ref()
-            .unwrap()
+        0 => sub_packets(packet)?
             .iter()
-            .fold(0u64, |s, p| s + eval(p)),
-        1 => packet
-            .sub_packets
-            .as_ref()
-            .unwrap()
+            .try_fold(0u64, |s, p| s.checked_add(eval(p)?).ok_or_else(overflow)),
+        1 => sub_packets(packet)?
             .iter()
-            .fold(1u64, |s, p| s * eval(p)),
-        2 => packet
-            .sub_packets
-            .as_ref()
-            .unwrap()
+            .try_fold(1u64, |s, p| s.checked_mul(eval(p)?).ok_or_else(overflow)),
+        2 => sub_packets(packet)?
             .iter()
-            .fold(u64::MAX, |s, p| {
-                let e = eval(p);
-                if e < s {
-                    e
-                } else {
-                    s
-                }
-            }),
-        3 => packet
-            .sub_packets
-            .as_ref()
-            .unwrap()
+            .try_fold(u64::MAX, |s, p| Ok(s.min(eval(p)?))),
+        3 => sub_packets(packet)?
             .iter()
-            .fold(u64::MIN, |s, p| {
-                let e = eval(p);
-                if e > s {
-                    e
-                } else {
-                    s
-                }
-            }),
-        4 => packet.literal.unwrap(),
-        5 => {
-            if eval(&packet.sub_packets.as_ref().unwrap()[0])
-                > eval(&packet.sub_packets.as_ref().unwrap()[1])
-            {
-                1
-            } else {
-                0
-            }
-        }
-        6 => {
-            if eval(&packet.sub_packets.as_ref().unwrap()[0])
-                < eval(&packet.sub_packets.as_ref().unwrap()[1])
-            {
-                1
-            } else {
-                0
-            }
-        }
-        7 => {
-            if eval(&packet.sub_packets.as_ref().unwrap()[0])
-                == eval(&packet.sub_packets.as_ref().unwrap()[1])
-            {
-                1
-            } else {
-                0
-            }
-        }
-        _ => panic!("unreachable"),
+            .try_fold(u64::MIN, |s, p| Ok(s.max(eval(p)?))),
+        4 => packet
+            .literal
+            .ok_or_else(|| Error::InvalidState("literal packet without a value".to_owned())),
+        5 => compare(packet, u64::gt),
+        6 => compare(packet, u64::lt),
+        7 => compare(packet, u64::eq),
+        type_id => Err(Error::InvalidState(format!(
+            "unknown packet type {}",
+            type_id
+        ))),
     }
 }
 
-fn part1(input: &[u8]) -> Result<String, Error> {
-    let (_, (packet, _)) = to_packets((input, 0)).unwrap();
+/// Decodes the outermost packet of a transmission. Errors point at the hex digit the
+/// offending bit is in.
+pub fn decode(input: &[u8]) -> Result<Packet, Error> {
+    let (rest, kind) = match to_packets((input, 0)) {
+        Ok((_, (packet, _))) => return Ok(packet),
+        Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => (e.input, e.code),
+        Err(nom::Err::Incomplete(_)) => ((&input[input.len()..], 0), ErrorKind::Eof),
+    };
+    let expected = match kind {
+        ErrorKind::Eof => "more bits, the transmission ends in the middle of a packet".to_owned(),
+        ErrorKind::TooLarge => "a literal value of at most 64 bits".to_owned(),
+        ErrorKind::LengthValue => "sub-packets that fill their operator's length".to_owned(),
+        ErrorKind::Many1 => format!("at most {} nested packets", MAX_DEPTH),
+        _ => "a packet".to_owned(),
+    };
+
+    let hex: String = input.iter().map(|b| format!("{:02X}", b)).collect();
+    let bit = (input.len() - rest.0.len()) * 8 + rest.1;
+    let offset = (bit / 4).min(hex.len());
+    Err(Error::Parse(Location::new(&hex, &hex[offset..], expected)))
+}
+
+pub fn part1(input: &[u8]) -> Result<String, Error> {
+    let packet = decode(input)?;
 
     Ok(format!("{}", add_version(&packet)))
 }
 
-fn part2(input: &[u8]) -> Result<String, Error> {
-    let (_, (packet, _)) = to_packets((input, 0)).unwrap();
+pub fn part2(input: &[u8]) -> Result<String, Error> {
+    let packet = decode(input)?;
 
-    Ok(format!("{}", eval(&packet)))
+    Ok(format!("{}", eval(&packet)?))
 }
 
 #[cfg(test)]
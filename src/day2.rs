@@ -1,74 +1,697 @@
+#[cfg(test)]
+use crate::generate::{generate, Size};
+use crate::input::for_each_line;
+use crate::output::Format;
+use crate::parse::{expect, finish, finish_at, many_to_end, tag, IResult, Location, ParseError};
+use crate::solver::{parse_param, unknown_param, Solver};
 use crate::Error;
-use nom::bytes::complete::tag;
-use nom::character::complete::{alpha1, digit1};
-use nom::combinator::{map_res, recognize};
-use nom::multi::many0;
-use nom::IResult;
-use std::fs::read_to_string;
+use nom::bytes::complete::take_while1;
+use nom::character::complete::{digit1, space0};
+use nom::combinator::{map_res, opt, recognize};
+use nom::sequence::pair;
+use std::fmt::Display;
+use std::io::BufRead;
+use std::str::FromStr;
+#[cfg(test)]
+use Command::*;
 
-pub fn calculate() -> Result<(String, String), Error> {
-    let input = parse_input(&read_to_string("input/day2")?)?;
+#[derive(Default)]
+pub struct Day2 {
+    pub rules: Rules,
+}
+
+impl Solver for Day2 {
+    type Input = Vec<Command>;
+
+    fn day(&self) -> u8 {
+        2
+    }
+
+    fn name(&self) -> &'static str {
+        "Dive!"
+    }
+
+    fn parse(&self, input: &str) -> Result<Self::Input, Error> {
+        parse_input(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Result<String, Error> {
+        solve(input, Plain, &self.rules)
+    }
+
+    fn part2(&self, input: &Self::Input) -> Result<String, Error> {
+        solve(input, Aim, &self.rules)
+    }
+
+    fn params(&self) -> &'static [&'static str] {
+        &["integer_width", "strict"]
+    }
+
+    fn set_param(&mut self, key: &str, value: &str) -> Result<(), Error> {
+        match key {
+            "integer_width" => self.rules.width = value.parse().map_err(Error::Config)?,
+            "strict" => self.rules.strict = parse_param(key, value, false..=true)?,
+            _ => return Err(unknown_param(self.day(), key, self.params())),
+        }
+
+        Ok(())
+    }
 
-    Ok((part1(&input)?, part2(&input)?))
+    fn stream(&self, reader: &mut dyn BufRead) -> Option<Result<(String, String), Error>> {
+        Some(stream(reader, &self.rules))
+    }
+
+    fn report(&self, input: &Self::Input) -> Option<Result<String, Error>> {
+        Some(report(input, &self.rules))
+    }
+
+    fn export(
+        &self,
+        input: &Self::Input,
+        parts: &[u8],
+        format: &Format,
+    ) -> Option<Result<String, Error>> {
+        let traces: Result<Vec<(u8, Vec<Position>)>, Error> = parts
+            .iter()
+            .map(|part| Ok((*part, part_trajectory(input, *part, &self.rules)?)))
+            .collect();
+
+        Some(traces.map(|traces| match format {
+            Format::Text => to_text(&traces),
+            Format::Csv => to_csv(&traces),
+            Format::Json => to_geojson(&traces),
+        }))
+    }
 }
 
-enum Direction {
-    Up,
-    Down,
+/// One instruction of a command script.
+#[derive(PartialEq, Clone, Debug)]
+pub enum Command {
+    Forward(i64),
+    Back(i64),
+    Down(i64),
+    Up(i64),
+    /// Turns the submarine around, so that forward and back swap directions.
+    Turn,
+    SetAim(i64),
+    Repeat(u32, Vec<Command>),
+}
+
+enum Keyword {
     Forward,
+    Back,
+    Down,
+    Up,
+    Turn,
+    SetAim,
+    Repeat,
 }
 
-impl TryFrom<&str> for Direction {
-    type Error = crate::Error;
+impl TryFrom<&str> for Keyword {
+    type Error = String;
 
     fn try_from(i: &str) -> Result<Self, Self::Error> {
         Ok(match i.to_lowercase().as_str() {
-            "up" => Direction::Up,
-            "down" => Direction::Down,
-            "forward" => Direction::Forward,
-            _ => return Err(Error::GenericDyn(format!("unknown word {}", i))),
+            "forward" => Keyword::Forward,
+            "back" => Keyword::Back,
+            "down" => Keyword::Down,
+            "up" => Keyword::Up,
+            "turn" => Keyword::Turn,
+            "set-aim" => Keyword::SetAim,
+            "repeat" => Keyword::Repeat,
+            _ => return Err(format!("unknown command '{}'", i)),
+        })
+    }
+}
+
+/// How deep `repeat` blocks may be nested.
+const MAX_NESTING: usize = 32;
+/// How many steps a script may take, counting the commands of a `repeat` every time
+/// they run, so a few nested repeats can't keep the submarine busy forever.
+const MAX_STEPS: u64 = 10_000_000;
+
+/// The steps `commands` take. A pass through an empty `repeat` counts as one step.
+fn steps(commands: &[Command]) -> u64 {
+    commands
+        .iter()
+        .map(|c| match c {
+            Command::Repeat(times, body) => (*times as u64).saturating_mul(steps(body).max(1)),
+            _ => 1,
         })
+        .fold(0, u64::saturating_add)
+}
+
+/// Adds the steps of `command` to `total`, false once that's over `MAX_STEPS`.
+fn within_budget(total: &mut u64, command: &Command) -> bool {
+    *total = total.saturating_add(steps(std::slice::from_ref(command)));
+    *total <= MAX_STEPS
+}
+
+fn too_many_steps() -> String {
+    format!("at most {} steps", MAX_STEPS)
+}
+
+fn amount<T>(input: &str) -> IResult<&str, T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let (rest, _) = tag(" ")(input)?;
+    map_res(recognize(digit1), str::parse)(rest)
+}
+
+fn signed_amount(input: &str) -> IResult<&str, i64> {
+    let (rest, _) = tag(" ")(input)?;
+    map_res(recognize(pair(opt(tag("-")), digit1)), str::parse)(rest)
+}
+
+/// The commands of a `repeat` block, up to and including the closing brace.
+fn block(input: &str, depth: usize) -> IResult<&str, Vec<Command>> {
+    let mut commands = vec![];
+    let mut rest = input;
+    loop {
+        let (after_indent, _) = space0(rest)?;
+        if let Ok((after, _)) = tag("}")(after_indent) {
+            let (after, _) = tag("\n")(after)?;
+            return Ok((after, commands));
+        }
+        if after_indent.is_empty() {
+            return Err(nom::Err::Error(ParseError {
+                input: after_indent,
+                expected: "'}'".to_owned(),
+            }));
+        }
+        let (after, command) = nested_command(rest, depth)?;
+        commands.push(command);
+        rest = after;
     }
 }
 
-fn my_i32_pair(input: &str) -> IResult<&str, (i32, i32)> {
-    let (rest, direction) = map_res(recognize(alpha1), Direction::try_from)(input)?;
-    let (rest, _) = tag(" ")(rest)?;
-    let (rest, amount) = map_res(recognize(digit1), str::parse)(rest)?;
+fn nested_command(input: &str, depth: usize) -> IResult<&str, Command> {
+    let (rest, _) = space0(input)?;
+    let (rest, keyword) = map_res(
+        expect(
+            take_while1(|c: char| c.is_ascii_alphabetic() || c == '-'),
+            "a command",
+        ),
+        Keyword::try_from,
+    )(rest)?;
+
+    let (rest, command) = match keyword {
+        Keyword::Forward => amount(rest).map(|(r, a)| (r, Command::Forward(a)))?,
+        Keyword::Back => amount(rest).map(|(r, a)| (r, Command::Back(a)))?,
+        Keyword::Down => amount(rest).map(|(r, a)| (r, Command::Down(a)))?,
+        Keyword::Up => amount(rest).map(|(r, a)| (r, Command::Up(a)))?,
+        Keyword::Turn => (rest, Command::Turn),
+        Keyword::SetAim => signed_amount(rest).map(|(r, a)| (r, Command::SetAim(a)))?,
+        Keyword::Repeat => {
+            if depth >= MAX_NESTING {
+                return Err(nom::Err::Failure(ParseError {
+                    input,
+                    expected: format!("at most {} nested repeats", MAX_NESTING),
+                }));
+            }
+            let (rest, times) = amount(rest)?;
+            let (rest, _) = tag(" {\n")(rest)?;
+            let (rest, body) = block(rest, depth + 1)?;
+            let repeat = Command::Repeat(times, body);
+            if !within_budget(&mut 0, &repeat) {
+                return Err(nom::Err::Failure(ParseError {
+                    input,
+                    expected: too_many_steps(),
+                }));
+            }
+            return Ok((rest, repeat));
+        }
+    };
     let (rest, _) = tag("\n")(rest)?;
 
-    match direction {
-        Direction::Up => Ok((rest, (0, amount))),
-        Direction::Down => Ok((rest, (0, -amount))),
-        Direction::Forward => Ok((rest, (amount, 0))),
+    Ok((rest, command))
+}
+
+fn command(input: &str) -> IResult<&str, Command> {
+    nested_command(input, 0)
+}
+
+fn multi(i: &str) -> IResult<&str, Vec<Command>> {
+    let mut total = 0;
+    many_to_end(move |input| {
+        let (rest, command) = command(input)?;
+        if !within_budget(&mut total, &command) {
+            return Err(nom::Err::Failure(ParseError {
+                input,
+                expected: too_many_steps(),
+            }));
+        }
+        Ok((rest, command))
+    })(i)
+}
+
+pub fn parse_input(input: &str) -> Result<Vec<Command>, Error> {
+    finish(input, multi)
+}
+
+/// Where the submarine is and which way it's pointing.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Position {
+    pub horizontal: i128,
+    pub depth: i128,
+    pub aim: i128,
+    /// 1 while pointing forward, -1 after turning around.
+    pub heading: i128,
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Position {
+            horizontal: 0,
+            depth: 0,
+            aim: 0,
+            heading: 1,
+        }
+    }
+}
+
+/// The integer type the position has to fit in.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Width {
+    I64,
+    I128,
+}
+
+impl FromStr for Width {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "i64" => Ok(Width::I64),
+            "i128" => Ok(Width::I128),
+            _ => Err(format!(
+                "unknown integer width '{}', expected i64 or i128",
+                s
+            )),
+        }
+    }
+}
+
+impl Display for Width {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Width::I64 => write!(f, "i64"),
+            Width::I128 => write!(f, "i128"),
+        }
+    }
+}
+
+impl Width {
+    fn contains(&self, value: i128) -> bool {
+        match self {
+            Width::I64 => i64::try_from(value).is_ok(),
+            Width::I128 => true,
+        }
+    }
+}
+
+/// What the submarine is checked against after every command.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Rules {
+    pub width: Width,
+    /// Fail when the submarine rises above the surface, instead of carrying on at a
+    /// negative depth.
+    pub strict: bool,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            width: Width::I64,
+            strict: false,
+        }
+    }
+}
+
+/// How the submarine interprets the movement commands. Both return `None` when the
+/// position overflows.
+pub trait Model {
+    /// Handles `down`, or `up` with a negative `amount`.
+    fn dive(&self, position: &mut Position, amount: i128) -> Option<()>;
+    /// Moves `distance` along the heading, a negative `distance` moves back.
+    fn advance(&self, position: &mut Position, distance: i128) -> Option<()>;
+}
+
+/// Part 1: `down` and `up` change the depth directly.
+pub struct Plain;
+
+impl Model for Plain {
+    fn dive(&self, position: &mut Position, amount: i128) -> Option<()> {
+        position.depth = position.depth.checked_add(amount)?;
+        Some(())
+    }
+
+    fn advance(&self, position: &mut Position, distance: i128) -> Option<()> {
+        position.horizontal = position
+            .horizontal
+            .checked_add(position.heading.checked_mul(distance)?)?;
+        Some(())
+    }
+}
+
+/// Part 2: `down` and `up` change the aim, and moving changes the depth by the aim.
+pub struct Aim;
+
+impl Model for Aim {
+    fn dive(&self, position: &mut Position, amount: i128) -> Option<()> {
+        position.aim = position.aim.checked_add(amount)?;
+        Some(())
+    }
+
+    fn advance(&self, position: &mut Position, distance: i128) -> Option<()> {
+        position.horizontal = position
+            .horizontal
+            .checked_add(position.heading.checked_mul(distance)?)?;
+        position.depth = position
+            .depth
+            .checked_add(position.aim.checked_mul(distance)?)?;
+        Some(())
+    }
+}
+
+pub struct Submarine<M: Model> {
+    pub position: Position,
+    model: M,
+    rules: Rules,
+    /// Number of commands run so far.
+    steps: usize,
+    trace: Option<Vec<Position>>,
+}
+
+impl<M: Model> Submarine<M> {
+    pub fn new(model: M) -> Submarine<M> {
+        Submarine::with_rules(model, Rules::default())
+    }
+
+    pub fn with_rules(model: M, rules: Rules) -> Submarine<M> {
+        Submarine {
+            position: Position::default(),
+            model,
+            rules,
+            steps: 0,
+            trace: None,
+        }
+    }
+
+    /// Like `with_rules`, but remembers the position after every command, see `trace`.
+    pub fn recording(model: M, rules: Rules) -> Submarine<M> {
+        Submarine {
+            trace: Some(vec![Position::default()]),
+            ..Submarine::with_rules(model, rules)
+        }
+    }
+
+    /// The starting position followed by the position after every command, with the
+    /// commands of a `repeat` counted each time they run. Empty unless recording.
+    pub fn trace(&self) -> &[Position] {
+        self.trace.as_deref().unwrap_or(&[])
+    }
+
+    /// Runs `command`, failing when the new position breaks the rules.
+    pub fn execute(&mut self, command: &Command) -> Result<(), Error> {
+        if let Command::Repeat(times, body) = command {
+            for _ in 0..*times {
+                self.run(body)?;
+            }
+            return Ok(());
+        }
+
+        self.steps += 1;
+        let position = &mut self.position;
+        let moved = match command {
+            Command::Forward(distance) => self.model.advance(position, *distance as i128),
+            Command::Back(distance) => self.model.advance(position, -(*distance as i128)),
+            Command::Down(amount) => self.model.dive(position, *amount as i128),
+            Command::Up(amount) => self.model.dive(position, -(*amount as i128)),
+            Command::Turn => {
+                position.heading = -position.heading;
+                Some(())
+            }
+            Command::SetAim(aim) => {
+                position.aim = *aim as i128;
+                Some(())
+            }
+            // Handled above, a repeat is never a step of its own.
+            Command::Repeat(..) => unreachable!(),
+        };
+
+        let fits = [position.horizontal, position.depth, position.aim]
+            .iter()
+            .all(|v| self.rules.width.contains(*v));
+        if moved.is_none() || !fits {
+            return Err(Error::InvalidState(format!(
+                "the position after step {} doesn't fit in {}",
+                self.steps, self.rules.width
+            )));
+        }
+        if self.rules.strict && position.depth < 0 {
+            return Err(Error::InvalidState(format!(
+                "the submarine surfaced at step {}, {} above the surface",
+                self.steps, -position.depth
+            )));
+        }
+
+        if let Some(trace) = &mut self.trace {
+            trace.push(self.position.clone());
+        }
+        Ok(())
+    }
+
+    pub fn run(&mut self, commands: &[Command]) -> Result<(), Error> {
+        commands.iter().try_for_each(|c| self.execute(c))
+    }
+
+    /// The puzzle answer, the horizontal position multiplied by the depth.
+    pub fn answer(&self) -> Result<String, Error> {
+        let Position {
+            horizontal, depth, ..
+        } = self.position;
+
+        horizontal
+            .checked_abs()
+            .zip(depth.checked_abs())
+            .and_then(|(h, d)| h.checked_mul(d))
+            .filter(|answer| self.rules.width.contains(*answer))
+            .map(|answer| answer.to_string())
+            .ok_or_else(|| {
+                Error::InvalidState(format!("the answer doesn't fit in {}", self.rules.width))
+            })
     }
 }
 
-fn multi(i: &str) -> IResult<&str, Vec<(i32, i32)>> {
-    many0(my_i32_pair)(i)
+/// Runs the commands with `model` and returns the answer.
+pub fn solve<M: Model>(input: &[Command], model: M, rules: &Rules) -> Result<String, Error> {
+    let mut submarine = Submarine::with_rules(model, rules.clone());
+    submarine.run(input)?;
+
+    submarine.answer()
+}
+
+pub fn part1(input: &[Command]) -> Result<String, Error> {
+    solve(input, Plain, &Rules::default())
+}
+
+pub fn part2(input: &[Command]) -> Result<String, Error> {
+    solve(input, Aim, &Rules::default())
+}
+
+/// Every position of the submarine with `model`, see [`Submarine::trace`].
+pub fn trajectory<M: Model>(
+    input: &[Command],
+    model: M,
+    rules: &Rules,
+) -> Result<Vec<Position>, Error> {
+    let mut submarine = Submarine::recording(model, rules.clone());
+    submarine.run(input)?;
+
+    Ok(submarine.trace().to_vec())
+}
+
+/// The trajectory with the model of `part`.
+fn part_trajectory(input: &[Command], part: u8, rules: &Rules) -> Result<Vec<Position>, Error> {
+    match part {
+        1 => trajectory(input, Plain, rules),
+        _ => trajectory(input, Aim, rules),
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct Summary {
+    /// Number of commands run, not counting the starting position.
+    pub steps: usize,
+    pub max_depth: i128,
+    /// The first step at which `max_depth` was reached.
+    pub max_depth_step: usize,
+    pub end: Position,
+}
+
+pub fn summarize(trace: &[Position]) -> Option<Summary> {
+    let (max_depth_step, deepest) =
+        trace
+            .iter()
+            .enumerate()
+            .fold(
+                None,
+                |deepest: Option<(usize, &Position)>, (step, p)| match deepest {
+                    Some((_, d)) if d.depth >= p.depth => deepest,
+                    _ => Some((step, p)),
+                },
+            )?;
+
+    Some(Summary {
+        steps: trace.len() - 1,
+        max_depth: deepest.depth,
+        max_depth_step,
+        end: trace.last()?.clone(),
+    })
+}
+
+fn describe(part: u8, trace: &[Position]) -> String {
+    match summarize(trace) {
+        Some(summary) => format!(
+            "part {}: {} steps, max depth {} at step {}, ends at horizontal {} depth {} aim {}\n",
+            part,
+            summary.steps,
+            summary.max_depth,
+            summary.max_depth_step,
+            summary.end.horizontal,
+            summary.end.depth,
+            summary.end.aim
+        ),
+        None => format!("part {}: no steps\n", part),
+    }
+}
+
+/// Summarizes the route of the submarine for both parts.
+pub fn report(input: &[Command], rules: &Rules) -> Result<String, Error> {
+    [1, 2]
+        .iter()
+        .map(|part| Ok(describe(*part, &part_trajectory(input, *part, rules)?)))
+        .collect()
+}
+
+/// The traces of the selected parts as a summary followed by a table.
+pub fn to_text(traces: &[(u8, Vec<Position>)]) -> String {
+    let mut text = String::new();
+    for (part, trace) in traces {
+        text.push_str(&describe(*part, trace));
+        text.push_str(&format!(
+            "{:>8} {:>11} {:>11} {:>11} {:>7}\n",
+            "step", "horizontal", "depth", "aim", "heading"
+        ));
+        for (step, p) in trace.iter().enumerate() {
+            text.push_str(&format!(
+                "{:>8} {:>11} {:>11} {:>11} {:>7}\n",
+                step, p.horizontal, p.depth, p.aim, p.heading
+            ));
+        }
+    }
+
+    text
 }
 
-fn parse_input(input: &str) -> Result<Vec<(i32, i32)>, Error> {
-    let (_, data) = multi(input)?;
+pub fn to_csv(traces: &[(u8, Vec<Position>)]) -> String {
+    let mut csv = "part,step,horizontal,depth,aim,heading\n".to_owned();
+    for (part, trace) in traces {
+        for (step, p) in trace.iter().enumerate() {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{}\n",
+                part, step, p.horizontal, p.depth, p.aim, p.heading
+            ));
+        }
+    }
 
-    Ok(data)
+    csv
 }
 
-fn part1(input: &[(i32, i32)]) -> Result<String, Error> {
-    let sum = input
+/// The traces as a GeoJSON feature collection with a line string per part, going
+/// through the (horizontal, depth) coordinates. The summary is in the properties.
+pub fn to_geojson(traces: &[(u8, Vec<Position>)]) -> String {
+    let features: Vec<String> = traces
         .iter()
-        .fold((0i32, 0i32), |sum, val| (sum.0 + val.0, sum.1 + val.1));
+        .map(|(part, trace)| {
+            let coordinates: Vec<String> = trace
+                .iter()
+                .map(|p| format!("[{},{}]", p.horizontal, p.depth))
+                .collect();
+            let properties = match summarize(trace) {
+                Some(s) => format!(
+                    "{{\"part\":{},\"steps\":{},\"max_depth\":{},\"max_depth_step\":{}}}",
+                    part, s.steps, s.max_depth, s.max_depth_step
+                ),
+                None => format!("{{\"part\":{}}}", part),
+            };
+            format!(
+                "{{\"type\":\"Feature\",\"properties\":{},\"geometry\":{{\"type\":\"LineString\",\"coordinates\":[{}]}}}}",
+                properties,
+                coordinates.join(",")
+            )
+        })
+        .collect();
 
-    Ok(format!("{}", sum.0.abs() * sum.1.abs()))
+    format!(
+        "{{\"type\":\"FeatureCollection\",\"features\":[{}]}}\n",
+        features.join(",")
+    )
 }
 
-fn part2(input: &[(i32, i32)]) -> Result<String, Error> {
-    let sum = input.iter().fold((0i32, 0i32, 0i32), |sum, val| {
-        let aim = sum.2 + val.1;
-        (sum.0 + val.0, sum.1 + (aim * val.0), aim)
-    });
+/// Solves both parts in one pass without keeping the commands. A `repeat` block is held
+/// in memory until it's closed.
+pub fn stream(reader: &mut dyn BufRead, rules: &Rules) -> Result<(String, String), Error> {
+    let mut plain = Submarine::with_rules(Plain, rules.clone());
+    let mut aim = Submarine::with_rules(Aim, rules.clone());
+    let mut block = String::new();
+    let mut block_start = 0;
+    let mut open = 0usize;
+    let mut total = 0;
+
+    for_each_line(reader, |number, line| {
+        let trimmed = line.trim();
+        if open == 0 {
+            block_start = number;
+        }
+        if trimmed.ends_with('{') {
+            open += 1;
+        } else if trimmed == "}" {
+            open = open.saturating_sub(1);
+        }
+
+        block.push_str(line);
+        if open == 0 {
+            let command = finish_at(block_start, &block, command)?;
+            if !within_budget(&mut total, &command) {
+                return Err(Error::Parse(Location::starting_at(
+                    block_start,
+                    &block,
+                    &block,
+                    too_many_steps(),
+                )));
+            }
+            plain.execute(&command)?;
+            aim.execute(&command)?;
+            block.clear();
+        }
+
+        Ok(())
+    })?;
+
+    if !block.is_empty() {
+        finish_at(block_start, &block, command)?;
+    }
 
-    Ok(format!("{}", sum.0.abs() * sum.1.abs()))
+    Ok((plain.answer()?, aim.answer()?))
+}
+
+#[cfg(test)]
+fn example() -> Vec<Command> {
+    vec![Forward(5), Down(5), Forward(8), Up(3), Down(8), Forward(2)]
 }
 
 #[test]
@@ -83,24 +706,343 @@ forward 2
 ",
     );
 
+    assert_eq!(example(), res.unwrap());
+}
+
+#[test]
+pub fn test_parse_extended_commands() {
+    let res = parse_input(
+        "back 2
+turn
+set-aim -4
+repeat 3 {
+  forward 1
+  repeat 2 {
+    down 1
+  }
+}
+",
+    );
+
     assert_eq!(
-        vec![(5, 0), (0, -5), (8, 0), (0, 3), (0, -8), (2, 0)],
+        vec![
+            Back(2),
+            Turn,
+            SetAim(-4),
+            Repeat(3, vec![Forward(1), Repeat(2, vec![Down(1)])]),
+        ],
         res.unwrap()
     );
 }
 
+#[test]
+pub fn test_parse_unclosed_repeat() {
+    match parse_input("repeat 2 {\nforward 1\n") {
+        Err(Error::Parse(location)) => assert_eq!(3, location.line),
+        _ => panic!("expected a parse error"),
+    }
+}
+
+#[test]
+pub fn test_parse_nesting_limit() {
+    let input = format!("{}{}", "repeat 1 {\n".repeat(40), "}\n".repeat(40));
+
+    assert!(parse_input(&input).is_err());
+}
+
+#[test]
+pub fn test_parse_step_limit() {
+    let nested = "repeat 4000000000 {\n  repeat 4000000000 {\n    forward 1\n  }\n}\n";
+    let empty = "forward 1\nrepeat 4000000000 {\n  repeat 4000000000 {\n  }\n}\n";
+    let total = "repeat 6000000 {\n  forward 1\n}\n".repeat(2);
+
+    for (input, line) in [(nested, 2), (empty, 3), (total.as_str(), 4)] {
+        match parse_input(input) {
+            Err(Error::Parse(location)) => {
+                assert_eq!(line, location.line);
+                assert_eq!("at most 10000000 steps", location.expected);
+            }
+            other => panic!("expected too many steps, got {:?}", other),
+        }
+    }
+    assert!(parse_input(&total[..total.len() / 2]).is_ok());
+}
+
+#[test]
+pub fn test_stream_step_limit() {
+    let input = "forward 1\nrepeat 4000000000 {\n  repeat 4000000000 {\n  }\n}\n";
+
+    match stream(&mut input.as_bytes(), &Rules::default()) {
+        Err(Error::Parse(location)) => assert_eq!(3, location.line),
+        other => panic!("expected too many steps, got {:?}", other),
+    }
+}
+
 #[test]
 pub fn test_part1() {
+    assert_eq!("150", part1(&example()).unwrap());
+}
+
+#[test]
+pub fn test_part2() {
+    assert_eq!("900", part2(&example()).unwrap());
+}
+
+#[test]
+pub fn test_execute_plain() {
+    let mut submarine = Submarine::new(Plain);
+    submarine
+        .run(&[Forward(5), Turn, Forward(2), Back(4), Down(3), SetAim(7)])
+        .unwrap();
+
+    assert_eq!(7, submarine.position.horizontal);
+    assert_eq!(3, submarine.position.depth);
+    assert_eq!(7, submarine.position.aim);
+    assert_eq!(-1, submarine.position.heading);
+}
+
+#[test]
+pub fn test_execute_aim() {
+    let mut submarine = Submarine::new(Aim);
+    submarine
+        .run(&[SetAim(2), Repeat(3, vec![Forward(1)]), Turn, Back(2), Up(1)])
+        .unwrap();
+
+    assert_eq!(5, submarine.position.horizontal);
+    assert_eq!(2, submarine.position.depth);
+    assert_eq!(1, submarine.position.aim);
+}
+
+#[test]
+pub fn test_stream() {
+    let mut input = "forward 5\ndown 5\nforward 8\nup 3\ndown 8\nforward 2\n".as_bytes();
+
     assert_eq!(
-        "150",
-        part1(&vec![(5, 0), (0, -5), (8, 0), (0, 3), (0, -8), (2, 0)]).unwrap()
+        ("150".to_owned(), "900".to_owned()),
+        stream(&mut input, &Rules::default()).unwrap()
     );
 }
 
 #[test]
-pub fn test_part2() {
+pub fn test_stream_matches_batch_with_repeats() {
+    let input = "down 1\nrepeat 2 {\n  forward 3\n  repeat 2 {\n    down 1\n  }\n}\nback 1\n";
+    let commands = parse_input(input).unwrap();
+
+    assert_eq!(
+        (part1(&commands).unwrap(), part2(&commands).unwrap()),
+        stream(&mut input.as_bytes(), &Rules::default()).unwrap()
+    );
+}
+
+#[test]
+pub fn test_stream_unclosed_repeat() {
+    assert!(stream(&mut "repeat 2 {\nforward 1\n".as_bytes(), &Rules::default()).is_err());
+}
+
+#[test]
+pub fn test_trace_only_when_recording() {
+    let mut submarine = Submarine::new(Plain);
+    submarine.run(&example()).unwrap();
+
+    assert!(submarine.trace().is_empty());
+}
+
+#[test]
+pub fn test_trajectory() {
+    let trace = trajectory(
+        &[Forward(2), Repeat(2, vec![Down(1)]), Turn],
+        Plain,
+        &Rules::default(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        vec![(0, 0, 1), (2, 0, 1), (2, 1, 1), (2, 2, 1), (2, 2, -1)],
+        trace
+            .iter()
+            .map(|p| (p.horizontal, p.depth, p.heading))
+            .collect::<Vec<(i128, i128, i128)>>()
+    );
+}
+
+#[test]
+pub fn test_trajectory_ends_at_the_answer() {
+    let commands = long_route(3);
+    for (part, answer) in [(1, part1(&commands)), (2, part2(&commands))] {
+        let end = part_trajectory(&commands, part, &Rules::default())
+            .unwrap()
+            .pop()
+            .unwrap();
+
+        assert_eq!(answer.unwrap(), (end.horizontal * end.depth).to_string());
+    }
+}
+
+#[test]
+pub fn test_summarize() {
+    let trace = trajectory(&example(), Aim, &Rules::default()).unwrap();
+
+    assert_eq!(
+        Some(Summary {
+            steps: 6,
+            max_depth: 60,
+            max_depth_step: 6,
+            end: Position {
+                horizontal: 15,
+                depth: 60,
+                aim: 10,
+                heading: 1
+            },
+        }),
+        summarize(&trace)
+    );
+    assert_eq!(None, summarize(&[]));
+}
+
+#[test]
+pub fn test_to_csv() {
+    let csv = to_csv(&[(
+        1,
+        trajectory(&[Forward(5), Down(5)], Plain, &Rules::default()).unwrap(),
+    )]);
+
+    assert_eq!(
+        "part,step,horizontal,depth,aim,heading\n1,0,0,0,0,1\n1,1,5,0,0,1\n1,2,5,5,0,1\n",
+        csv
+    );
+}
+
+#[test]
+pub fn test_to_geojson() {
+    let json = to_geojson(&[(
+        2,
+        trajectory(&[Down(2), Forward(3)], Aim, &Rules::default()).unwrap(),
+    )]);
+
+    assert_eq!(
+        "{\"type\":\"FeatureCollection\",\"features\":[{\"type\":\"Feature\",\"properties\":{\"part\":2,\"steps\":2,\"max_depth\":6,\"max_depth_step\":2},\"geometry\":{\"type\":\"LineString\",\"coordinates\":[[0,0],[0,0],[3,6]]}}]}\n",
+        json
+    );
+}
+
+#[cfg(test)]
+fn rules(width: Width, strict: bool) -> Rules {
+    Rules { width, strict }
+}
+
+/// A long generated route, which never rises above the surface.
+#[cfg(test)]
+fn long_route(seed: u64) -> Vec<Command> {
+    let size = Size {
+        count: Some(100_000),
+        ..Size::default()
+    };
+    parse_input(&generate(2, seed, &size).unwrap()).unwrap()
+}
+
+#[test]
+pub fn test_long_route_overflows_i64() {
+    let size = Size {
+        count: Some(100_000),
+        ..Size::default()
+    };
+    let route = generate(2, 1, &size).unwrap();
+    let input = format!("down 1000000\nrepeat 20 {{\n{}}}\n", route);
+    let commands = parse_input(&input).unwrap();
+
+    let mut day = Day2::default();
+    match day.part2(&commands) {
+        Err(Error::InvalidState(message)) => {
+            assert_eq!("the answer doesn't fit in i64", message)
+        }
+        other => panic!("expected an overflow, got {:?}", other),
+    }
+    day.set_param("integer_width", "i128").unwrap();
+    assert!(day.part2(&commands).unwrap().parse::<i128>().unwrap() > i64::MAX as i128);
+}
+
+#[test]
+pub fn test_overflow_is_an_error() {
+    let mut input = "down 1000000000\n".to_owned();
+    input.push_str(&"forward 1000000000\n".repeat(10));
+    let commands = parse_input(&input).unwrap();
+
+    match solve(&commands, Aim, &rules(Width::I64, false)) {
+        Err(Error::InvalidState(message)) => {
+            assert_eq!("the position after step 11 doesn't fit in i64", message)
+        }
+        other => panic!("expected an overflow, got {:?}", other),
+    }
+    assert_eq!(
+        "100000000000000000000000000000",
+        solve(&commands, Aim, &rules(Width::I128, false)).unwrap()
+    );
+}
+
+#[test]
+pub fn test_answer_overflow_is_an_error() {
+    let commands = parse_input("forward 4294967296\ndown 4294967296\n").unwrap();
+
+    assert!(solve(&commands, Plain, &rules(Width::I64, false)).is_err());
+    assert_eq!(
+        "18446744073709551616",
+        solve(&commands, Plain, &rules(Width::I128, false)).unwrap()
+    );
+}
+
+#[test]
+pub fn test_i128_overflow_is_an_error() {
+    let commands = vec![
+        Down(i64::MAX),
+        Repeat(4, vec![Forward(i64::MAX)]),
+        Repeat(1 << 20, vec![Forward(i64::MAX)]),
+    ];
+
+    assert!(solve(&commands, Aim, &rules(Width::I128, false)).is_err());
+}
+
+#[test]
+pub fn test_widths_agree_on_long_routes() {
+    for seed in 0..3 {
+        let commands = long_route(seed);
+        for strict in [false, true] {
+            assert_eq!(
+                part1(&commands).unwrap(),
+                solve(&commands, Plain, &rules(Width::I128, strict)).unwrap()
+            );
+            assert_eq!(
+                part2(&commands).unwrap(),
+                solve(&commands, Aim, &rules(Width::I128, strict)).unwrap()
+            );
+        }
+    }
+}
+
+#[test]
+pub fn test_strict_mode_rejects_surfacing() {
+    let mut commands = long_route(7);
+    let mut submarine = Submarine::new(Plain);
+    submarine.run(&commands).unwrap();
+    let depth = submarine.position.depth as i64;
+    commands.push(Up(depth + 3));
+
+    assert!(solve(&commands, Plain, &rules(Width::I64, false)).is_ok());
+    match solve(&commands, Plain, &rules(Width::I64, true)) {
+        Err(Error::InvalidState(message)) => assert_eq!(
+            "the submarine surfaced at step 100001, 3 above the surface",
+            message
+        ),
+        other => panic!("expected the submarine to surface, got {:?}", other),
+    }
+}
+
+#[test]
+pub fn test_strict_mode_with_aim() {
+    let commands = vec![Forward(1), Up(2), Forward(1)];
+
     assert_eq!(
-        "900",
-        part2(&vec![(5, 0), (0, -5), (8, 0), (0, 3), (0, -8), (2, 0)]).unwrap()
+        "4",
+        solve(&commands, Aim, &rules(Width::I64, false)).unwrap()
     );
+    assert!(solve(&commands, Aim, &rules(Width::I64, true)).is_err());
 }
@@ -1,147 +1,381 @@
+use crate::parse::{expect, finish, many_to_end, tag, IResult};
+use crate::solver::Solver;
 use crate::Error;
-use gmp::mpz::Mpz;
-use nom::bytes::complete::tag;
-use nom::character::complete::digit1;
-use nom::combinator::{map, map_res, recognize};
-use nom::multi::many0;
-use nom::IResult;
-use std::fs::read_to_string;
+use nom::bytes::complete::take_while1;
+use nom::combinator::map;
 
-pub fn calculate() -> Result<(String, String), Error> {
-    let input_t = parse_input_transposed(&read_to_string("input/day3")?)?;
-    let input = parse_input(&read_to_string("input/day3")?)?;
+pub struct Day3;
 
-    Ok((part1(&(&input_t.0, input_t.1))?, part2(&input, input_t.1)?))
+impl Solver for Day3 {
+    type Input = BitMatrix;
+
+    fn day(&self) -> u8 {
+        3
+    }
+
+    fn name(&self) -> &'static str {
+        "Binary Diagnostic"
+    }
+
+    fn parse(&self, input: &str) -> Result<Self::Input, Error> {
+        parse_input(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Result<String, Error> {
+        part1(input)
+    }
+
+    fn part2(&self, input: &Self::Input) -> Result<String, Error> {
+        part2(input)
+    }
+
+    fn report(&self, input: &Self::Input) -> Option<Result<String, Error>> {
+        Some(report(input))
+    }
 }
 
-fn from_str_to_chars(input: &str) -> Vec<char> {
-    input.chars().collect()
+/// A set of rows of a [`BitMatrix`], one bit per row.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Rows {
+    words: Vec<u64>,
 }
 
-fn chars(input: &str) -> IResult<&str, Vec<char>> {
-    let (rest, data) = map(recognize(digit1), from_str_to_chars)(input)?;
-    let (rest, _) = tag("\n")(rest)?;
+impl Rows {
+    /// Number of rows in the set.
+    pub fn len(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
 
-    Ok((rest, data))
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|w| *w == 0)
+    }
+
+    /// The lowest row in the set.
+    pub fn first(&self) -> Option<usize> {
+        self.words
+            .iter()
+            .enumerate()
+            .find(|(_, w)| **w != 0)
+            .map(|(i, w)| i * 64 + w.trailing_zeros() as usize)
+    }
 }
 
-fn char_grid(i: &str) -> IResult<&str, Vec<Vec<char>>> {
-    many0(chars)(i)
+/// The diagnostic report as a matrix of bits of any width. It's stored column by column,
+/// so the ones in a column can be counted, and rows filtered on a column, a word at a
+/// time. Column 0 is the leftmost, most significant, bit.
+#[derive(PartialEq, Debug)]
+pub struct BitMatrix {
+    width: usize,
+    height: usize,
+    /// Words per column, bit `r % 64` of word `r / 64` is row `r`.
+    words: usize,
+    columns: Vec<u64>,
 }
 
-fn parse_input_transposed(input: &str) -> Result<(Vec<(Mpz, usize)>, usize), Error> {
-    let (_, data) = char_grid(input)?;
+impl BitMatrix {
+    pub fn from_rows(rows: &[Vec<bool>]) -> Result<BitMatrix, Error> {
+        let width = match rows.first() {
+            Some(first) => first.len(),
+            None => return Err(Error::InvalidState("the report has no rows".to_owned())),
+        };
+        let words = rows.len().div_ceil(64);
+
+        let mut columns = vec![0; width * words];
+        for (r, row) in rows.iter().enumerate() {
+            if row.len() != width {
+                return Err(Error::InvalidState(format!(
+                    "row {} has {} bits, expected {}",
+                    r + 1,
+                    row.len(),
+                    width
+                )));
+            }
+            for (c, bit) in row.iter().enumerate() {
+                if *bit {
+                    columns[c * words + r / 64] |= 1 << (r % 64);
+                }
+            }
+        }
+
+        Ok(BitMatrix {
+            width,
+            height: rows.len(),
+            words,
+            columns,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    fn column(&self, column: usize) -> &[u64] {
+        &self.columns[column * self.words..(column + 1) * self.words]
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> bool {
+        self.column(column)[row / 64] & (1 << (row % 64)) != 0
+    }
 
-    let mut transposed: Vec<String> = vec!["".to_owned(); data[0].len()];
-    for r in &data {
-        for (i, c) in r.iter().enumerate() {
-            transposed[i].push(*c);
+    pub fn all_rows(&self) -> Rows {
+        let mut words = vec![u64::MAX; self.words];
+        if let Some(last) = words.last_mut() {
+            if !self.height.is_multiple_of(64) {
+                *last = (1 << (self.height % 64)) - 1;
+            }
         }
+
+        Rows { words }
     }
 
-    Ok((
-        transposed
+    /// Number of `rows` with a one in `column`.
+    pub fn count_ones(&self, column: usize, rows: &Rows) -> usize {
+        self.column(column)
             .iter()
-            .map(|s| (gmp::mpz::Mpz::from_str_radix(s, 2).unwrap(), s.len()))
-            .collect(),
-        transposed.len(),
-    ))
+            .zip(&rows.words)
+            .map(|(c, r)| (c & r).count_ones() as usize)
+            .sum()
+    }
+
+    /// The `rows` that have `bit` in `column`.
+    pub fn filter(&self, rows: &Rows, column: usize, bit: bool) -> Rows {
+        Rows {
+            words: self
+                .column(column)
+                .iter()
+                .zip(&rows.words)
+                .map(|(c, r)| if bit { c & r } else { !c & r })
+                .collect(),
+        }
+    }
+
+    /// `row` as a number, `None` if it's wider than 128 bits.
+    pub fn row_value(&self, row: usize) -> Option<u128> {
+        if self.width > 128 {
+            return None;
+        }
+
+        Some((0..self.width).fold(0, |value, c| value << 1 | self.get(row, c) as u128))
+    }
 }
 
-fn from_str_to_u32(input: &str) -> Result<u32, Error> {
-    Ok(u32::from_str_radix(input, 2)?)
+fn binary(input: &str) -> IResult<&str, &str> {
+    expect(take_while1(|c| c == '0' || c == '1'), "a binary digit")(input)
 }
 
-fn row(input: &str) -> IResult<&str, u32> {
-    let (rest, data) = map_res(recognize(digit1), from_str_to_u32)(input)?;
+fn from_str_to_bits(input: &str) -> Vec<bool> {
+    input.chars().map(|c| c == '1').collect()
+}
+
+fn bits(input: &str) -> IResult<&str, Vec<bool>> {
+    let (rest, data) = map(binary, from_str_to_bits)(input)?;
     let (rest, _) = tag("\n")(rest)?;
 
     Ok((rest, data))
 }
 
-fn multi(i: &str) -> IResult<&str, Vec<u32>> {
-    many0(row)(i)
+fn bit_grid(i: &str) -> IResult<&str, Vec<Vec<bool>>> {
+    many_to_end(bits)(i)
+}
+
+pub fn parse_input(input: &str) -> Result<BitMatrix, Error> {
+    BitMatrix::from_rows(&finish(input, bit_grid)?)
 }
 
-fn parse_input(input: &str) -> Result<Vec<u32>, Error> {
-    let (_, data) = multi(input)?;
+/// Both answers multiply two numbers as wide as the report, which only fits for
+/// reports of at most 64 bits.
+fn check_width(input: &BitMatrix) -> Result<(), Error> {
+    if input.width() > 64 {
+        return Err(Error::InvalidState(format!(
+            "the answer for a {} bit report doesn't fit in 128 bits",
+            input.width()
+        )));
+    }
 
-    Ok(data)
+    Ok(())
 }
 
-fn part1(input: &(&[(Mpz, usize)], usize)) -> Result<String, Error> {
-    let mut gamma: u64 = 0;
-    let mut epsilon: u64 = 0;
+pub fn part1(input: &BitMatrix) -> Result<String, Error> {
+    check_width(input)?;
+    let all = input.all_rows();
+    let mut gamma: u128 = 0;
+    let mut epsilon: u128 = 0;
 
-    for (i, (m, s)) in input.0.iter().enumerate() {
-        if m.popcount() > (s / 2) {
-            gamma += 2u64.pow((input.1 - i - 1).try_into().unwrap());
-        } else {
-            epsilon += 2u64.pow((input.1 - i - 1).try_into().unwrap());
-        }
+    for column in 0..input.width() {
+        let most_common = input.count_ones(column, &all) > input.height() / 2;
+        gamma = gamma << 1 | most_common as u128;
+        epsilon = epsilon << 1 | !most_common as u128;
     }
     Ok(format!("{}", gamma * epsilon))
 }
 
-fn part2(input: &[u32], width: usize) -> Result<String, Error> {
-    let mut rows: Vec<u32> = input.to_vec();
-    let mut o2_generator = 0;
-    for i in (0..width).rev() {
-        let popcount = rows
-            .iter()
-            .filter(|num| *num & 2u32.pow(i.try_into().unwrap()) != 0)
-            .count();
-        let popcount_inv = rows.len() - popcount;
-        rows = rows
-            .iter()
-            .filter(|num| {
-                if popcount >= popcount_inv {
-                    *num & 2u32.pow(i.try_into().unwrap()) != 0
-                } else {
-                    *num & 2u32.pow(i.try_into().unwrap()) == 0
-                }
-            })
-            .copied()
-            .collect();
+/// Which bit a [`Criterion`] keeps at each position.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Keep {
+    MostCommon,
+    LeastCommon,
+}
 
-        if rows.len() == 1 {
-            o2_generator = rows[0];
-            break;
+/// The order a [`Criterion`] visits the bit positions in.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Order {
+    MsbFirst,
+    LsbFirst,
+}
+
+/// How to narrow the report down to a single row, one bit position at a time.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Criterion {
+    pub keep: Keep,
+    /// The bit to keep when there are as many ones as zeros.
+    pub tie: bool,
+    pub order: Order,
+}
+
+impl Criterion {
+    pub const OXYGEN_GENERATOR: Criterion = Criterion {
+        keep: Keep::MostCommon,
+        tie: true,
+        order: Order::MsbFirst,
+    };
+    pub const CO2_SCRUBBER: Criterion = Criterion {
+        keep: Keep::LeastCommon,
+        tie: false,
+        order: Order::MsbFirst,
+    };
+
+    /// The bit to keep among `ones` ones and `zeros` zeros. When every row has the same
+    /// bit that's the one kept, even for the least common.
+    fn bit(&self, ones: usize, zeros: usize) -> bool {
+        if ones == 0 || zeros == 0 {
+            return ones > 0;
+        }
+        match (ones.cmp(&zeros), self.keep) {
+            (std::cmp::Ordering::Equal, _) => self.tie,
+            (ordering, Keep::MostCommon) => ordering.is_gt(),
+            (ordering, Keep::LeastCommon) => ordering.is_lt(),
         }
     }
+}
 
-    let mut rows: Vec<u32> = input.to_vec();
-    let mut co2_scrubber = 0;
-    for i in (0..width).rev() {
-        let popcount = rows
-            .iter()
-            .filter(|num| *num & 2u32.pow(i.try_into().unwrap()) != 0)
-            .count();
-        let popcount_inv = rows.len() - popcount;
-        rows = rows
-            .iter()
-            .filter(|num| {
-                if popcount >= popcount_inv {
-                    *num & 2u32.pow(i.try_into().unwrap()) == 0
-                } else {
-                    *num & 2u32.pow(i.try_into().unwrap()) != 0
-                }
-            })
-            .copied()
-            .collect();
+/// One bit position visited while filtering.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Step {
+    pub column: usize,
+    pub ones: usize,
+    pub zeros: usize,
+    pub kept: bool,
+    /// The rows left after the step.
+    pub remaining: usize,
+}
 
-        if rows.len() == 1 {
-            co2_scrubber = rows[0];
+/// The outcome of [`filter_by_bit_criteria`]: the row that's left, if exactly one is,
+/// and every step that led there.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Filtered {
+    pub row: Option<usize>,
+    pub trace: Vec<Step>,
+}
+
+/// Filters `rows` on their lowest `width` bits by `criterion`, until a single row is
+/// left or the bits run out.
+pub fn filter_by_bit_criteria(
+    rows: &BitMatrix,
+    width: usize,
+    criterion: &Criterion,
+) -> Result<Filtered, Error> {
+    if width > rows.width() {
+        return Err(Error::InvalidState(format!(
+            "can't filter on {} bits of a {} bit report",
+            width,
+            rows.width()
+        )));
+    }
+
+    let columns = rows.width() - width..rows.width();
+    let columns: Box<dyn Iterator<Item = usize>> = match criterion.order {
+        Order::MsbFirst => Box::new(columns),
+        Order::LsbFirst => Box::new(columns.rev()),
+    };
+
+    let mut remaining = rows.all_rows();
+    let mut trace = vec![];
+    for column in columns {
+        if remaining.len() == 1 {
             break;
         }
+        let ones = rows.count_ones(column, &remaining);
+        let zeros = remaining.len() - ones;
+        let kept = criterion.bit(ones, zeros);
+        remaining = rows.filter(&remaining, column, kept);
+        trace.push(Step {
+            column,
+            ones,
+            zeros,
+            kept,
+            remaining: remaining.len(),
+        });
     }
 
+    Ok(Filtered {
+        row: Some(remaining)
+            .filter(|r| r.len() == 1)
+            .and_then(|r| r.first()),
+        trace,
+    })
+}
+
+fn rating(input: &BitMatrix, criterion: &Criterion) -> Result<u128, Error> {
+    filter_by_bit_criteria(input, input.width(), criterion)?
+        .row
+        .and_then(|r| input.row_value(r))
+        .ok_or(Error::NoSolution("the rows can't be narrowed down to one"))
+}
+
+pub fn part2(input: &BitMatrix) -> Result<String, Error> {
+    check_width(input)?;
+    let o2_generator = rating(input, &Criterion::OXYGEN_GENERATOR)?;
+    let co2_scrubber = rating(input, &Criterion::CO2_SCRUBBER)?;
+
     Ok(format!("{}", o2_generator * co2_scrubber))
 }
 
-#[test]
-pub fn test_parse_input_transposed() {
-    let res = parse_input_transposed(
+/// The steps both life support ratings take through the report.
+pub fn report(input: &BitMatrix) -> Result<String, Error> {
+    let mut report = String::new();
+    for (name, criterion) in [
+        ("oxygen generator", Criterion::OXYGEN_GENERATOR),
+        ("CO2 scrubber", Criterion::CO2_SCRUBBER),
+    ] {
+        let filtered = filter_by_bit_criteria(input, input.width(), &criterion)?;
+        report.push_str(&format!("{} rating:\n", name));
+        for step in &filtered.trace {
+            report.push_str(&format!(
+                "  bit {}: {} ones, {} zeros, kept {}, {} rows left\n",
+                step.column, step.ones, step.zeros, step.kept as u8, step.remaining
+            ));
+        }
+        match filtered.row {
+            Some(row) => {
+                let bits: String = (0..input.width())
+                    .map(|c| if input.get(row, c) { '1' } else { '0' })
+                    .collect();
+                report.push_str(&format!("  row {}: {}\n", row + 1, bits));
+            }
+            None => report.push_str("  no single row is left\n"),
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+fn example() -> BitMatrix {
+    parse_input(
         "00100
 11110
 10110
@@ -155,69 +389,156 @@ pub fn test_parse_input_transposed() {
 00010
 01010
 ",
-    );
+    )
+    .unwrap()
+}
 
+#[test]
+pub fn test_parse_input() {
+    let matrix = example();
+
+    assert_eq!((5, 12), (matrix.width(), matrix.height()));
     assert_eq!(
-        (
-            vec![
-                (Mpz::from(1948), 12),
-                (Mpz::from(1109), 12),
-                (Mpz::from(4080), 12),
-                (Mpz::from(1891), 12),
-                (Mpz::from(484), 12)
-            ],
-            5
-        ),
-        res.unwrap()
+        vec![4, 30, 22, 23, 21, 15, 7, 28, 16, 25, 2, 10],
+        (0..12)
+            .map(|r| matrix.row_value(r).unwrap())
+            .collect::<Vec<u128>>()
     );
 }
 
 #[test]
-pub fn test_parse_input() {
-    let res = parse_input(
-        "00100
-11110
-10110
-10111
-10101
-01111
-00111
-11100
-10000
-11001
-00010
-01010
-",
-    );
+pub fn test_parse_ragged_rows() {
+    match parse_input("101\n10\n") {
+        Err(Error::InvalidState(message)) => assert_eq!("row 2 has 2 bits, expected 3", message),
+        _ => panic!("expected ragged rows to be rejected"),
+    }
+}
+
+#[test]
+pub fn test_count_ones() {
+    let matrix = example();
+    let all = matrix.all_rows();
 
     assert_eq!(
-        vec![4, 30, 22, 23, 21, 15, 7, 28, 16, 25, 2, 10],
-        res.unwrap()
+        vec![7, 5, 8, 7, 5],
+        (0..5)
+            .map(|c| matrix.count_ones(c, &all))
+            .collect::<Vec<usize>>()
     );
 }
 
+#[test]
+pub fn test_filter() {
+    let matrix = example();
+    let rows = matrix.filter(&matrix.all_rows(), 0, true);
+    let rows = matrix.filter(&rows, 1, false);
+
+    assert_eq!(4, rows.len());
+    assert_eq!(Some(2), rows.first());
+    assert_eq!(3, matrix.count_ones(2, &rows));
+
+    let rows = matrix.filter(&rows, 4, true);
+    assert_eq!(Some(4), matrix.filter(&rows, 3, false).first());
+    assert!(matrix.filter(&rows, 2, false).is_empty());
+}
+
+#[test]
+pub fn test_wide_report() {
+    let mut input = String::new();
+    for r in 0..100 {
+        let row: String = (0..200)
+            .map(|c| if (r * c) % 3 == 0 { '1' } else { '0' })
+            .collect();
+        input.push_str(&row);
+        input.push('\n');
+    }
+    let matrix = parse_input(&input).unwrap();
+
+    assert_eq!((200, 100), (matrix.width(), matrix.height()));
+    assert_eq!(100, matrix.count_ones(0, &matrix.all_rows()));
+    assert_eq!(34, matrix.count_ones(199, &matrix.all_rows()));
+    assert!(matrix.get(99, 198));
+    assert_eq!(None, matrix.row_value(0));
+    assert!(part1(&matrix).is_err());
+}
+
 #[test]
 pub fn test_part1() {
+    assert_eq!("198", part1(&example()).unwrap());
+}
+
+#[test]
+pub fn test_part2_shared_bit() {
+    // Both rows left for the CO2 scrubber rating share their middle bit.
+    let matrix = parse_input("100\n101\n011\n010\n").unwrap();
+
+    assert_eq!("10", part2(&matrix).unwrap());
+}
+
+#[test]
+pub fn test_part2() {
+    assert_eq!("230", part2(&example()).unwrap());
+}
+
+#[test]
+pub fn test_filter_by_bit_criteria_trace() {
+    let filtered = filter_by_bit_criteria(&example(), 5, &Criterion::OXYGEN_GENERATOR).unwrap();
+
+    assert_eq!(Some(3), filtered.row);
     assert_eq!(
-        "198",
-        part1(&(
-            &vec![
-                (Mpz::from(1948), 12),
-                (Mpz::from(1109), 12),
-                (Mpz::from(4080), 12),
-                (Mpz::from(1891), 12),
-                (Mpz::from(484), 12)
-            ],
-            5
-        ))
-        .unwrap()
+        vec![
+            (0, true, 7),
+            (1, false, 4),
+            (2, true, 3),
+            (3, true, 2),
+            (4, true, 1)
+        ],
+        filtered
+            .trace
+            .iter()
+            .map(|s| (s.column, s.kept, s.remaining))
+            .collect::<Vec<_>>()
     );
+    assert_eq!((5, 3), (filtered.trace[0].zeros, filtered.trace[1].ones));
 }
 
 #[test]
-pub fn test_part2() {
+pub fn test_filter_by_bit_criteria_lsb_first() {
+    let criterion = Criterion {
+        order: Order::LsbFirst,
+        ..Criterion::CO2_SCRUBBER
+    };
+    let filtered = filter_by_bit_criteria(&example(), 5, &criterion).unwrap();
+
+    // 5 ones in the last column, so the rows ending in 1 are kept.
+    assert_eq!(
+        (4, true),
+        (filtered.trace[0].column, filtered.trace[0].kept)
+    );
+    assert_eq!(Some(9), filtered.row);
+}
+
+#[test]
+pub fn test_filter_by_bit_criteria_narrower() {
+    let matrix = example();
+    let filtered = filter_by_bit_criteria(&matrix, 2, &Criterion::OXYGEN_GENERATOR).unwrap();
+
+    // Only the lowest two bits are looked at, and four rows end in 10.
+    assert_eq!(None, filtered.row);
     assert_eq!(
-        "230",
-        part2(&vec![4, 30, 22, 23, 21, 15, 7, 28, 16, 25, 2, 10], 5).unwrap()
+        vec![3, 4],
+        filtered.trace.iter().map(|s| s.column).collect::<Vec<_>>()
     );
+    assert!(filter_by_bit_criteria(&matrix, 6, &Criterion::OXYGEN_GENERATOR).is_err());
+}
+
+#[test]
+pub fn test_report() {
+    let report = report(&example()).unwrap();
+
+    assert!(report
+        .contains("oxygen generator rating:\n  bit 0: 7 ones, 5 zeros, kept 1, 7 rows left\n"));
+    assert!(report.contains("  row 4: 10111\n"));
+    assert!(report.contains("CO2 scrubber rating:\n"));
+    assert!(report.contains("  row 12: 01010\n"));
 }
@@ -1,25 +1,76 @@
+use crate::parse::{finish, many_to_end, tag, IResult, ParseError};
+use crate::solver::{parse_param, unknown_param, Solver};
 use crate::Error;
 use nom::branch::alt;
-use nom::bytes::complete::tag;
 use nom::character::complete::digit1;
 use nom::combinator::{map_res, recognize};
 use nom::multi::many0;
-use nom::IResult;
-use std::fs::read_to_string;
 
-pub fn calculate() -> Result<(String, String), Error> {
-    let input = parse_input(&read_to_string("input/day4")?)?;
+pub struct Day4 {
+    /// Width and height of the bingo boards.
+    pub size: usize,
+}
 
-    Ok((part1(&input)?, part2(&input)?))
+impl Default for Day4 {
+    fn default() -> Self {
+        Day4 { size: 5 }
+    }
+}
+
+impl Solver for Day4 {
+    type Input = (Vec<u8>, Vec<Board>);
+
+    fn day(&self) -> u8 {
+        4
+    }
+
+    fn name(&self) -> &'static str {
+        "Giant Squid"
+    }
+
+    fn parse(&self, input: &str) -> Result<Self::Input, Error> {
+        parse_input_sized(input, self.size)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Result<String, Error> {
+        part1(input)
+    }
+
+    fn part2(&self, input: &Self::Input) -> Result<String, Error> {
+        part2(input)
+    }
+
+    fn params(&self) -> &'static [&'static str] {
+        &["size"]
+    }
+
+    fn set_param(&mut self, key: &str, value: &str) -> Result<(), Error> {
+        match key {
+            "size" => self.size = parse_param(key, value, 1..=100)?,
+            _ => return Err(unknown_param(self.day(), key, self.params())),
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Clone)]
-struct Board {
-    numbers: [u8; 25],
-    marks: [bool; 25],
+pub struct Board {
+    pub size: usize,
+    pub numbers: Vec<u8>,
+    pub marks: Vec<bool>,
 }
 
 impl Board {
+    /// A board without marks, `numbers` are the `size` by `size` cells row by row.
+    pub fn new(size: usize, numbers: Vec<u8>) -> Board {
+        Board {
+            size,
+            marks: vec![false; numbers.len()],
+            numbers,
+        }
+    }
+
     fn mark_number(&mut self, num: u8) -> Option<u32> {
         for (i, v) in self.numbers.iter().enumerate() {
             if num == *v {
@@ -27,13 +78,13 @@ impl Board {
 
                 let mut vertical = true;
                 let mut horizontal = true;
-                let x = i % 5;
-                let y = i / 5;
-                for index in 0..=4 {
-                    if !self.marks[x + index * 5] {
+                let x = i % self.size;
+                let y = i / self.size;
+                for index in 0..self.size {
+                    if !self.marks[x + index * self.size] {
                         vertical = false;
                     }
-                    if !self.marks[index + y * 5] {
+                    if !self.marks[index + y * self.size] {
                         horizontal = false;
                     }
                 }
@@ -78,32 +129,43 @@ fn read_board_numbers(input: &str) -> IResult<&str, Vec<u8>> {
     many0(u8_space)(input)
 }
 
-fn board(input: &str) -> IResult<&str, Board> {
-    let (rest, _) = alt((tag("\n "), tag("\n\n "), tag("\n\n"), tag("\n")))(input)?;
-    let (rest, numbers) = read_board_numbers(rest)?;
+fn board<'a>(size: usize) -> impl Fn(&'a str) -> IResult<&'a str, Board> {
+    move |input: &'a str| {
+        let (start, _) = alt((tag("\n "), tag("\n\n "), tag("\n\n"), tag("\n")))(input)?;
+        let (rest, numbers) = read_board_numbers(start)?;
+        if numbers.len() != size * size {
+            return Err(nom::Err::Error(ParseError {
+                input: start,
+                expected: format!("a board of {} numbers, got {}", size * size, numbers.len()),
+            }));
+        }
 
-    Ok((
-        rest,
-        Board {
-            numbers: numbers.try_into().unwrap(),
-            marks: [false; 25],
-        },
-    ))
+        Ok((rest, Board::new(size, numbers)))
+    }
 }
 
-fn read_boards(input: &str) -> IResult<&str, Vec<Board>> {
-    many0(board)(input)
+fn read_boards(input: &str, size: usize) -> IResult<&str, Vec<Board>> {
+    many_to_end(board(size))(input)
 }
 
-fn parse_input(input: &str) -> Result<(Vec<u8>, Vec<Board>), Error> {
+fn bingo(input: &str, size: usize) -> IResult<&str, (Vec<u8>, Vec<Board>)> {
     let (rest, numbers) = read_numbers(input)?;
     let (rest, _) = tag("\n")(rest)?;
-    let (_, boards) = read_boards(rest)?;
+    let (rest, boards) = read_boards(rest, size)?;
 
-    Ok((numbers, boards))
+    Ok((rest, (numbers, boards)))
 }
 
-fn part1(input: &(Vec<u8>, Vec<Board>)) -> Result<String, Error> {
+pub fn parse_input(input: &str) -> Result<(Vec<u8>, Vec<Board>), Error> {
+    parse_input_sized(input, 5)
+}
+
+/// Parses the draws and boards that are `size` by `size` numbers.
+pub fn parse_input_sized(input: &str, size: usize) -> Result<(Vec<u8>, Vec<Board>), Error> {
+    finish(input, |i| bingo(i, size))
+}
+
+pub fn part1(input: &(Vec<u8>, Vec<Board>)) -> Result<String, Error> {
     let mut boards = input.1.clone();
     for num in &input.0 {
         for board in &mut boards {
@@ -113,10 +175,10 @@ fn part1(input: &(Vec<u8>, Vec<Board>)) -> Result<String, Error> {
         }
     }
 
-    Err(Error::Generic("no winner"))
+    Err(Error::NoSolution("no bingo board ever wins"))
 }
 
-fn part2(input: &(Vec<u8>, Vec<Board>)) -> Result<String, Error> {
+pub fn part2(input: &(Vec<u8>, Vec<Board>)) -> Result<String, Error> {
     let mut boards = input.1.clone();
     let mut winning_boards = vec![];
     let mut last_win = None;
@@ -133,10 +195,10 @@ fn part2(input: &(Vec<u8>, Vec<Board>)) -> Result<String, Error> {
         }
     }
 
-    if last_win.is_some() {
-        return Ok(format!("{}", last_win.unwrap()));
+    if let Some(score) = last_win {
+        return Ok(format!("{}", score));
     }
-    Err(Error::Generic("no winner"))
+    Err(Error::NoSolution("no bingo board ever wins"))
 }
 
 #[test]
@@ -155,7 +217,7 @@ fn test_u8_comma() {
 
 #[test]
 fn test_board() {
-    let (_, board) = board(
+    let (_, board) = board(5)(
         "
 22 13 17 11  0
  8  2 23  4 24
@@ -240,27 +302,27 @@ fn test_part1() {
             3, 26, 1,
         ],
         vec![
-            Board {
-                numbers: [
+            Board::new(
+                5,
+                vec![
                     22, 13, 17, 11, 0, 8, 2, 23, 4, 24, 21, 9, 14, 16, 7, 6, 10, 3, 18, 5, 1, 12,
                     20, 15, 19,
                 ],
-                marks: [false; 25],
-            },
-            Board {
-                numbers: [
+            ),
+            Board::new(
+                5,
+                vec![
                     3, 15, 0, 2, 22, 9, 18, 13, 17, 5, 19, 8, 7, 25, 23, 20, 11, 10, 24, 4, 14, 21,
                     16, 12, 6,
                 ],
-                marks: [false; 25],
-            },
-            Board {
-                numbers: [
+            ),
+            Board::new(
+                5,
+                vec![
                     14, 21, 17, 24, 4, 10, 16, 15, 9, 19, 18, 8, 23, 26, 20, 22, 11, 13, 6, 5, 2,
                     0, 12, 3, 7,
                 ],
-                marks: [false; 25],
-            },
+            ),
         ],
     );
 
@@ -277,27 +339,27 @@ fn test_part2() {
             3, 26, 1,
         ],
         vec![
-            Board {
-                numbers: [
+            Board::new(
+                5,
+                vec![
                     22, 13, 17, 11, 0, 8, 2, 23, 4, 24, 21, 9, 14, 16, 7, 6, 10, 3, 18, 5, 1, 12,
                     20, 15, 19,
                 ],
-                marks: [false; 25],
-            },
-            Board {
-                numbers: [
+            ),
+            Board::new(
+                5,
+                vec![
                     3, 15, 0, 2, 22, 9, 18, 13, 17, 5, 19, 8, 7, 25, 23, 20, 11, 10, 24, 4, 14, 21,
                     16, 12, 6,
                 ],
-                marks: [false; 25],
-            },
-            Board {
-                numbers: [
+            ),
+            Board::new(
+                5,
+                vec![
                     14, 21, 17, 24, 4, 10, 16, 15, 9, 19, 18, 8, 23, 26, 20, 22, 11, 13, 6, 5, 2,
                     0, 12, 3, 7,
                 ],
-                marks: [false; 25],
-            },
+            ),
         ],
     );
 
@@ -305,3 +367,12 @@ fn test_part2() {
 
     assert_eq!("1924", res);
 }
+
+#[test]
+fn test_parse_input_sized() {
+    let (_, boards) = parse_input_sized("3,1\n\n1 2\n3 4\n\n5 6\n7 8\n", 2).unwrap();
+
+    assert_eq!(2, boards.len());
+    assert_eq!(vec![5, 6, 7, 8], boards[1].numbers);
+    assert!(parse_input_sized("3,1\n\n1 2\n3 4\n", 5).is_err());
+}
@@ -1,35 +1,63 @@
+use crate::input::for_each_line;
+use crate::parse::{finish, finish_at, many_to_end, tag, IResult};
+use crate::solver::Solver;
 use crate::Error;
-use nom::bytes::complete::tag;
 use nom::character::complete::digit1;
 use nom::combinator::{map_res, recognize};
-use nom::multi::many0;
-use nom::IResult;
 use std::cmp::Ordering;
 use std::collections::HashMap;
-use std::fs::read_to_string;
+use std::io::BufRead;
 
-pub fn calculate() -> Result<(String, String), Error> {
-    let input = parse_input(&read_to_string("input/day5")?)?;
+pub struct Day5;
 
-    Ok((part1(&input)?, part2(&input)?))
+impl Solver for Day5 {
+    type Input = Vec<Line>;
+
+    fn day(&self) -> u8 {
+        5
+    }
+
+    fn name(&self) -> &'static str {
+        "Hydrothermal Venture"
+    }
+
+    fn parse(&self, input: &str) -> Result<Self::Input, Error> {
+        parse_input(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Result<String, Error> {
+        part1(input)
+    }
+
+    fn part2(&self, input: &Self::Input) -> Result<String, Error> {
+        part2(input)
+    }
+
+    fn stream(&self, reader: &mut dyn BufRead) -> Option<Result<(String, String), Error>> {
+        Some(stream(reader))
+    }
 }
 
 #[derive(PartialEq, Debug)]
-struct Line {
-    x1: u32,
-    y1: u32,
-    x2: u32,
-    y2: u32,
+pub struct Line {
+    pub x1: u32,
+    pub y1: u32,
+    pub x2: u32,
+    pub y2: u32,
 }
 
 #[derive(PartialEq, Eq, Hash, Clone, Debug)]
-struct Point {
-    x: u32,
-    y: u32,
+pub struct Point {
+    pub x: u32,
+    pub y: u32,
 }
 
 impl Line {
-    fn points(&self) -> LineIter {
+    pub fn is_straight(&self) -> bool {
+        self.x1 == self.x2 || self.y1 == self.y2
+    }
+
+    pub fn points(&self) -> LineIter {
         LineIter {
             last_point: None,
             x1: self.x1,
@@ -40,7 +68,7 @@ impl Line {
     }
 }
 
-struct LineIter {
+pub struct LineIter {
     last_point: Option<Point>,
     x1: u32,
     y1: u32,
@@ -97,50 +125,64 @@ fn line(input: &str) -> IResult<&str, Line> {
 }
 
 fn multi(i: &str) -> IResult<&str, Vec<Line>> {
-    many0(line)(i)
+    many_to_end(line)(i)
 }
 
-fn parse_input(input: &str) -> Result<Vec<Line>, Error> {
-    let (_, data) = multi(input)?;
+pub fn parse_input(input: &str) -> Result<Vec<Line>, Error> {
+    finish(input, multi)
+}
 
-    Ok(data)
+/// Counts every point of `line` on the map, the first visit counting as 0.
+fn mark(points: &mut HashMap<Point, u32>, line: &Line) {
+    line.points().for_each(|p| {
+        if let Some(v) = points.get_mut(&p) {
+            *v += 1;
+        } else {
+            points.insert(p, 0);
+        }
+    })
 }
 
-fn part1(input: &[Line]) -> Result<String, Error> {
-    let straight: Vec<&Line> = input
-        .iter()
-        .filter(|l| l.x1 == l.x2 || l.y1 == l.y2)
-        .collect();
+fn overlaps(points: &HashMap<Point, u32>) -> usize {
+    points.values().filter(|v| **v > 0).count()
+}
 
+pub fn part1(input: &[Line]) -> Result<String, Error> {
     let mut points: HashMap<Point, u32> = HashMap::new();
 
-    straight.iter().for_each(|l| {
-        l.points().for_each(|p| {
-            if let Some(v) = points.get_mut(&p) {
-                *v += 1;
-            } else {
-                points.insert(p.clone(), 0);
-            }
-        })
-    });
+    input
+        .iter()
+        .filter(|l| l.is_straight())
+        .for_each(|l| mark(&mut points, l));
 
-    Ok(format!("{}", points.values().filter(|v| **v > 0).count()))
+    Ok(format!("{}", overlaps(&points)))
 }
 
-fn part2(input: &[Line]) -> Result<String, Error> {
+pub fn part2(input: &[Line]) -> Result<String, Error> {
     let mut points: HashMap<Point, u32> = HashMap::new();
 
-    input.iter().for_each(|l| {
-        l.points().for_each(|p| {
-            if let Some(v) = points.get_mut(&p) {
-                *v += 1;
-            } else {
-                points.insert(p.clone(), 0);
-            }
-        })
-    });
+    input.iter().for_each(|l| mark(&mut points, l));
+
+    Ok(format!("{}", overlaps(&points)))
+}
+
+/// Solves both parts in one pass, memory grows with the covered area instead of the
+/// number of lines.
+pub fn stream(reader: &mut dyn BufRead) -> Result<(String, String), Error> {
+    let mut straight: HashMap<Point, u32> = HashMap::new();
+    let mut all: HashMap<Point, u32> = HashMap::new();
+
+    for_each_line(reader, |number, text| {
+        let l = finish_at(number, text, line)?;
+        if l.is_straight() {
+            mark(&mut straight, &l);
+        }
+        mark(&mut all, &l);
+
+        Ok(())
+    })?;
 
-    Ok(format!("{}", points.values().filter(|v| **v > 0).count()))
+    Ok((overlaps(&straight).to_string(), overlaps(&all).to_string()))
 }
 
 #[test]
@@ -310,7 +352,7 @@ pub fn test_parse() {
 pub fn test_part1() {
     assert_eq!(
         "5",
-        part1(&vec![
+        part1(&[
             Line {
                 x1: 0,
                 y1: 9,
@@ -380,7 +422,7 @@ pub fn test_part1() {
 pub fn test_part2() {
     assert_eq!(
         "12",
-        part2(&vec![
+        part2(&[
             Line {
                 x1: 0,
                 y1: 9,
@@ -445,3 +487,24 @@ pub fn test_part2() {
         .unwrap()
     );
 }
+
+#[test]
+pub fn test_stream() {
+    let mut input = "0,9 -> 5,9
+8,0 -> 0,8
+9,4 -> 3,4
+2,2 -> 2,1
+7,0 -> 7,4
+6,4 -> 2,0
+0,9 -> 2,9
+3,4 -> 1,4
+0,0 -> 8,8
+5,5 -> 8,2
+"
+    .as_bytes();
+
+    assert_eq!(
+        ("5".to_owned(), "12".to_owned()),
+        stream(&mut input).unwrap()
+    );
+}
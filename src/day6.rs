@@ -1,78 +1,143 @@
+use crate::parse::{finish, many_to_end, tag, IResult};
+use crate::solver::{parse_param, unknown_param, Solver};
 use crate::Error;
 use nom::branch::alt;
-use nom::bytes::complete::tag;
 use nom::character::complete::digit1;
 use nom::combinator::{map_res, recognize};
-use nom::multi::many0;
-use nom::IResult;
 use std::collections::HashMap;
-use std::fs::read_to_string;
 
-pub fn calculate() -> Result<(String, String), Error> {
-    let input = parse_input(&read_to_string("input/day6")?)?;
+pub struct Day6 {
+    /// Days to simulate in part 1.
+    pub part1_days: u64,
+    /// Days to simulate in part 2.
+    pub part2_days: u64,
+}
 
-    Ok((part1(&input)?, part2(&input)?))
+impl Default for Day6 {
+    fn default() -> Self {
+        Day6 {
+            part1_days: 80,
+            part2_days: 256,
+        }
+    }
+}
+
+/// Longest simulation, the count of fish overflows 64 bits long before this anyway.
+const MAX_DAYS: u64 = 1000;
+
+impl Solver for Day6 {
+    type Input = Vec<u64>;
+
+    fn day(&self) -> u8 {
+        6
+    }
+
+    fn name(&self) -> &'static str {
+        "Lanternfish"
+    }
+
+    fn parse(&self, input: &str) -> Result<Self::Input, Error> {
+        parse_input(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Result<String, Error> {
+        fish_after(input, self.part1_days)
+    }
+
+    fn part2(&self, input: &Self::Input) -> Result<String, Error> {
+        fish_after(input, self.part2_days)
+    }
+
+    fn params(&self) -> &'static [&'static str] {
+        &["days", "part1_days", "part2_days"]
+    }
+
+    fn set_param(&mut self, key: &str, value: &str) -> Result<(), Error> {
+        match key {
+            "days" => {
+                self.part1_days = parse_param(key, value, 0..=MAX_DAYS)?;
+                self.part2_days = self.part1_days;
+            }
+            "part1_days" => self.part1_days = parse_param(key, value, 0..=MAX_DAYS)?,
+            "part2_days" => self.part2_days = parse_param(key, value, 0..=MAX_DAYS)?,
+            _ => return Err(unknown_param(self.day(), key, self.params())),
+        }
+
+        Ok(())
+    }
+}
+
+fn timer(input: &str) -> Result<u64, String> {
+    match input.parse() {
+        Ok(timer) if timer <= 8 => Ok(timer),
+        _ => Err(format!("timer {} is not between 0 and 8", input)),
+    }
 }
 
 fn my_u64(input: &str) -> IResult<&str, u64> {
-    let (rest, data) = map_res(recognize(digit1), str::parse)(input)?;
+    let (rest, data) = map_res(recognize(digit1), timer)(input)?;
     let (rest, _) = alt((tag(","), tag("\n")))(rest)?;
 
     Ok((rest, data))
 }
 
 fn multi(i: &str) -> IResult<&str, Vec<u64>> {
-    many0(my_u64)(i)
+    many_to_end(my_u64)(i)
 }
 
-fn parse_input(input: &str) -> Result<Vec<u64>, Error> {
-    let (_, data) = multi(input)?;
-
-    Ok(data)
+pub fn parse_input(input: &str) -> Result<Vec<u64>, Error> {
+    finish(input, multi)
 }
 
-fn calc_rec(init: u64, day: u64, cache: &mut HashMap<(u64, u64), u64>) -> u64 {
+fn calc_rec(init: u64, day: u64, cache: &mut HashMap<(u64, u64), u64>) -> Option<u64> {
     if day == 0 {
-        return 1;
+        return Some(1);
     }
 
     if init == 0 {
         if !cache.contains_key(&(6, day - 1)) {
-            let val = calc_rec(6, day - 1, cache);
+            let val = calc_rec(6, day - 1, cache)?;
             cache.insert((6, day - 1), val);
         }
         if !cache.contains_key(&(8, day - 1)) {
-            let val = calc_rec(8, day - 1, cache);
+            let val = calc_rec(8, day - 1, cache)?;
             cache.insert((8, day - 1), val);
         }
-        return *cache.get(&(6, day - 1)).unwrap() + *cache.get(&(8, day - 1)).unwrap();
+
+        cache
+            .get(&(6, day - 1))
+            .unwrap()
+            .checked_add(*cache.get(&(8, day - 1)).unwrap())
     } else {
         if !cache.contains_key(&(init - 1, day - 1)) {
-            let val = calc_rec(init - 1, day - 1, cache);
+            let val = calc_rec(init - 1, day - 1, cache)?;
             cache.insert((init - 1, day - 1), val);
         }
-        return *cache.get(&(init - 1, day - 1)).unwrap();
+
+        Some(*cache.get(&(init - 1, day - 1)).unwrap())
     }
 }
 
-fn part1(input: &[u64]) -> Result<String, Error> {
+/// Number of lanternfish after `days` days.
+pub fn fish_after(input: &[u64], days: u64) -> Result<String, Error> {
     let mut cache: HashMap<(u64, u64), u64> = HashMap::new();
 
     let result = input
         .iter()
-        .fold(0u64, |s, n| s + calc_rec(*n, 80, &mut cache));
+        .try_fold(0u64, |s, n| s.checked_add(calc_rec(*n, days, &mut cache)?))
+        .ok_or_else(|| {
+            Error::InvalidState(format!("the number of fish after {} days overflows", days))
+        })?;
 
     Ok(format!("{}", result))
 }
 
-fn part2(input: &[u64]) -> Result<String, Error> {
-    let mut cache: HashMap<(u64, u64), u64> = HashMap::new();
-
-    let result = input
-        .iter()
-        .fold(0u64, |s, n| s + calc_rec(*n, 256, &mut cache));
+pub fn part1(input: &[u64]) -> Result<String, Error> {
+    fish_after(input, 80)
+}
 
-    Ok(format!("{}", result))
+pub fn part2(input: &[u64]) -> Result<String, Error> {
+    fish_after(input, 256)
 }
 
 #[test]
@@ -84,10 +149,16 @@ pub fn test_parse() {
 
 #[test]
 pub fn test_part1() {
-    assert_eq!("5934", part1(&vec![3, 4, 3, 1, 2]).unwrap());
+    assert_eq!("5934", part1(&[3, 4, 3, 1, 2]).unwrap());
 }
 
 #[test]
 pub fn test_part2() {
-    assert_eq!("26984457539", part2(&vec![3, 4, 3, 1, 2]).unwrap());
+    assert_eq!("26984457539", part2(&[3, 4, 3, 1, 2]).unwrap());
+}
+
+#[test]
+pub fn test_fish_after() {
+    assert_eq!("26", fish_after(&[3, 4, 3, 1, 2], 18).unwrap());
+    assert!(fish_after(&[3, 4, 3, 1, 2], 1000).is_err());
 }
@@ -1,17 +1,35 @@
+use crate::parse::{finish, many_to_end, tag, IResult};
+use crate::solver::Solver;
 use crate::Error;
 use nom::branch::alt;
-use nom::bytes::complete::tag;
 use nom::character::complete::digit1;
 use nom::combinator::{map_res, recognize};
-use nom::multi::many0;
-use nom::IResult;
 use std::cmp::{max, min};
-use std::fs::read_to_string;
 
-pub fn calculate() -> Result<(String, String), Error> {
-    let input = parse_input(&read_to_string("input/day7")?)?;
+pub struct Day7;
 
-    Ok((part1(&input)?, part2(&input)?))
+impl Solver for Day7 {
+    type Input = Vec<u32>;
+
+    fn day(&self) -> u8 {
+        7
+    }
+
+    fn name(&self) -> &'static str {
+        "The Treachery of Whales"
+    }
+
+    fn parse(&self, input: &str) -> Result<Self::Input, Error> {
+        parse_input(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Result<String, Error> {
+        part1(input)
+    }
+
+    fn part2(&self, input: &Self::Input) -> Result<String, Error> {
+        part2(input)
+    }
 }
 
 fn my_u32(input: &str) -> IResult<&str, u32> {
@@ -22,48 +40,50 @@ fn my_u32(input: &str) -> IResult<&str, u32> {
 }
 
 fn multi(i: &str) -> IResult<&str, Vec<u32>> {
-    many0(my_u32)(i)
+    many_to_end(my_u32)(i)
 }
 
-fn parse_input(input: &str) -> Result<Vec<u32>, Error> {
-    let (_, data) = multi(input)?;
-
-    Ok(data)
+pub fn parse_input(input: &str) -> Result<Vec<u32>, Error> {
+    finish(input, multi)
 }
 
-fn part1(input: &[u32]) -> Result<String, Error> {
+pub fn part1(input: &[u32]) -> Result<String, Error> {
     let mut numbers = input.to_vec();
     numbers.sort_unstable();
     let mid = numbers.len() / 2;
 
-    let target = numbers[mid];
+    let target = *numbers
+        .get(mid)
+        .ok_or_else(|| Error::InvalidState("no crabs in input".to_owned()))?;
     let result = input
         .iter()
-        .fold(0u32, |s, v| s + max(target, *v) - min(target, *v));
+        .fold(0u64, |s, v| s + (max(target, *v) - min(target, *v)) as u64);
 
     Ok(format!("{}", result))
 }
 
-fn cost(steps: u32) -> u32 {
-    if steps == 0 {
-        0
-    } else {
-        steps * (steps + 1) / 2
-    }
+fn cost(steps: u32) -> u64 {
+    let steps = steps as u64;
+    steps * (steps + 1) / 2
 }
 
-fn part2(input: &[u32]) -> Result<String, Error> {
-    let min_crab = input.iter().min().unwrap();
-    let max_crab = input.iter().max().unwrap();
-
-    let mut costs = vec![vec![0; input.len()]; (*max_crab + 1) as usize];
-    for i in *min_crab..=*max_crab {
-        for (crab, v) in input.iter().enumerate() {
-            costs[i as usize][crab as usize] = cost(max(v, &i) - min(v, &i));
-        }
-    }
-
-    let result: u32 = costs.iter().map(|c| c.iter().sum()).min().unwrap();
+pub fn part2(input: &[u32]) -> Result<String, Error> {
+    let (min_crab, max_crab) = match (input.iter().min(), input.iter().max()) {
+        (Some(min_crab), Some(max_crab)) => (*min_crab, *max_crab),
+        _ => return Err(Error::InvalidState("no crabs in input".to_owned())),
+    };
+
+    // The best position is within half a step of the mean, so one position either side
+    // of its floor and ceiling covers it.
+    let mean = (input.iter().map(|v| *v as u128).sum::<u128>() / input.len() as u128) as u32;
+    let result = (mean.saturating_sub(1).max(min_crab)..=mean.saturating_add(2).min(max_crab))
+        .filter_map(|i| {
+            input
+                .iter()
+                .try_fold(0u64, |s, v| s.checked_add(cost(max(*v, i) - min(*v, i))))
+        })
+        .min()
+        .ok_or_else(|| Error::InvalidState("the fuel needed doesn't fit in 64 bits".to_owned()))?;
 
     Ok(format!("{}", result))
 }
@@ -84,10 +104,25 @@ pub fn test_parse() {
 
 #[test]
 pub fn test_part1() {
-    assert_eq!("37", part1(&vec![16, 1, 2, 0, 4, 2, 7, 1, 2, 14]).unwrap());
+    assert_eq!("37", part1(&[16, 1, 2, 0, 4, 2, 7, 1, 2, 14]).unwrap());
 }
 
 #[test]
 pub fn test_part2() {
-    assert_eq!("168", part2(&vec![16, 1, 2, 0, 4, 2, 7, 1, 2, 14]).unwrap());
+    assert_eq!("168", part2(&[16, 1, 2, 0, 4, 2, 7, 1, 2, 14]).unwrap());
+}
+
+#[test]
+pub fn test_part2_around_the_mean() {
+    assert_eq!("0", part2(&[5]).unwrap());
+    assert_eq!("1", part2(&[0, 1]).unwrap());
+    assert_eq!("48", part2(&[0, 0, 0, 0, 10]).unwrap());
+}
+
+#[test]
+pub fn test_part2_overflow() {
+    assert!(matches!(
+        part2(&[0, u32::MAX].repeat(8)),
+        Err(Error::InvalidState(_))
+    ));
 }
@@ -1,19 +1,38 @@
+use crate::parse::{finish, many_to_end, tag, IResult};
+use crate::solver::Solver;
 use crate::Error;
-use nom::bytes::complete::tag;
 use nom::character::complete::alpha1;
-use nom::multi::many0;
-use nom::IResult;
-use std::fs::read_to_string;
+use nom::combinator::map_res;
 
-pub fn calculate() -> Result<(String, String), Error> {
-    let input = parse_input(&read_to_string("input/day8")?)?;
+pub struct Day8;
 
-    Ok((part1(&input)?, part2(&input)?))
+impl Solver for Day8 {
+    type Input = Vec<Row>;
+
+    fn day(&self) -> u8 {
+        8
+    }
+
+    fn name(&self) -> &'static str {
+        "Seven Segment Search"
+    }
+
+    fn parse(&self, input: &str) -> Result<Self::Input, Error> {
+        parse_input(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Result<String, Error> {
+        part1(input)
+    }
+
+    fn part2(&self, input: &Self::Input) -> Result<String, Error> {
+        part2(input)
+    }
 }
 
 #[derive(PartialEq, Debug)]
-struct SignalPattern {
-    pattern: Vec<u8>,
+pub struct SignalPattern {
+    pub pattern: Vec<u8>,
 }
 
 impl SignalPattern {
@@ -30,62 +49,49 @@ impl SignalPattern {
     }
 }
 
-impl From<&str> for SignalPattern {
-    fn from(input: &str) -> Self {
-        let pattern = input
-            .chars()
-            .map(|c| match c {
-                'a' => 0,
-                'b' => 1,
-                'c' => 2,
-                'd' => 3,
-                'e' => 4,
-                'f' => 5,
-                'g' => 6,
-                _ => panic!("unreachable"),
-            })
-            .collect();
-
-        SignalPattern { pattern }
+fn segment(c: char) -> Result<u8, String> {
+    match c {
+        'a'..='g' => Ok(c as u8 - b'a'),
+        _ => Err(format!("segment '{}' is not between a and g", c)),
+    }
+}
+
+impl TryFrom<&str> for SignalPattern {
+    type Error = String;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        let pattern = input.chars().map(segment).collect::<Result<_, _>>()?;
+
+        Ok(SignalPattern { pattern })
     }
 }
 
 #[derive(PartialEq, Clone, Debug)]
-struct Digit {
-    sections: [u8; 7],
+pub struct Digit {
+    pub sections: [u8; 7],
 }
 
-impl From<&str> for Digit {
-    fn from(input: &str) -> Self {
+impl TryFrom<&str> for Digit {
+    type Error = String;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
         let mut digit = Digit { sections: [0; 7] };
-        input.chars().for_each(|c| match c {
-            'a' => digit.sections[0] = 1,
-            'b' => digit.sections[1] = 1,
-            'c' => digit.sections[2] = 1,
-            'd' => digit.sections[3] = 1,
-            'e' => digit.sections[4] = 1,
-            'f' => digit.sections[5] = 1,
-            'g' => digit.sections[6] = 1,
-            _ => panic!("unreachable"),
-        });
+        for c in input.chars() {
+            digit.sections[segment(c)? as usize] = 1;
+        }
 
-        digit
+        Ok(digit)
     }
 }
 
 impl From<&Vec<u8>> for Digit {
     fn from(input: &Vec<u8>) -> Self {
         let mut digit = Digit { sections: [0; 7] };
-        input.iter().for_each(|c| match c {
-            0 => digit.sections[0] = 1,
-            1 => digit.sections[1] = 1,
-            2 => digit.sections[2] = 1,
-            3 => digit.sections[3] = 1,
-            4 => digit.sections[4] = 1,
-            5 => digit.sections[5] = 1,
-            6 => digit.sections[6] = 1,
-            _ => panic!("unreachable"),
-        });
+        for c in input {
+            if let Some(section) = digit.sections.get_mut(*c as usize) {
+                *section = 1;
+            }
+        }
 
         digit
     }
@@ -116,9 +122,9 @@ impl Digit {
 }
 
 #[derive(PartialEq, Debug)]
-struct Row {
-    patterns: [SignalPattern; 10],
-    digits: [Digit; 4],
+pub struct Row {
+    pub patterns: [SignalPattern; 10],
+    pub digits: [Digit; 4],
 }
 
 impl Row {
@@ -133,7 +139,18 @@ impl Row {
         sum
     }
 
-    fn map(&self) -> [Digit; 10] {
+    /// The digit of the first pattern that `matches`.
+    fn deduce(&self, matches: impl FnMut(&&SignalPattern) -> bool) -> Result<Digit, Error> {
+        self.patterns
+            .iter()
+            .find(matches)
+            .map(|p| Digit::from(&p.pattern))
+            .ok_or(Error::NoSolution(
+                "the wiring can't be deduced from the patterns",
+            ))
+    }
+
+    fn map(&self) -> Result<[Digit; 10], Error> {
         let mut solutions = [Digit {
             sections: [0, 0, 0, 0, 0, 0, 0],
         }; 10];
@@ -153,92 +170,53 @@ impl Row {
             }
         }
 
-        solutions[6] = Digit::from(
-            &self
-                .patterns
-                .iter()
-                .find(|p| {
-                    p.pattern.len() == 6 && solutions[1].sections[p.missing()[0] as usize] == 1
-                })
-                .unwrap()
-                .pattern,
-        );
-        solutions[9] = Digit::from(
-            &self
-                .patterns
-                .iter()
-                .find(|p| {
-                    p.pattern.len() == 6 && solutions[4].sections[p.missing()[0] as usize] == 0
-                })
-                .unwrap()
-                .pattern,
-        );
-        solutions[0] = Digit::from(
-            &self
-                .patterns
-                .iter()
-                .find(|p| {
-                    if p.pattern.len() != 6 {
-                        return false;
-                    }
-                    let d = Digit::from(&p.pattern);
-                    d != solutions[6] && d != solutions[9]
-                })
-                .unwrap()
-                .pattern,
-        );
-        solutions[3] = Digit::from(
-            &self
-                .patterns
-                .iter()
-                .find(|p| {
-                    if p.pattern.len() != 5 {
-                        return false;
-                    }
-                    let missing = p.missing();
-                    solutions[1].sections[missing[0] as usize] == 0
-                        && solutions[1].sections[missing[1] as usize] == 0
-                })
-                .unwrap()
-                .pattern,
-        );
-        solutions[5] = Digit::from(
-            &self
-                .patterns
-                .iter()
-                .find(|p| {
-                    if p.pattern.len() != 5 {
-                        return false;
-                    }
-                    let missing = solutions[6].missing();
-                    !p.pattern.contains(&missing[0])
-                })
-                .unwrap()
-                .pattern,
-        );
-        solutions[2] = Digit::from(
-            &self
-                .patterns
-                .iter()
-                .find(|p| {
-                    if p.pattern.len() != 5 {
-                        return false;
-                    }
-                    let d = Digit::from(&p.pattern);
-                    d != solutions[3] && d != solutions[5]
-                })
-                .unwrap()
-                .pattern,
-        );
+        solutions[6] = self.deduce(|p| {
+            p.pattern.len() == 6 && solutions[1].sections[p.missing()[0] as usize] == 1
+        })?;
+        solutions[9] = self.deduce(|p| {
+            p.pattern.len() == 6 && solutions[4].sections[p.missing()[0] as usize] == 0
+        })?;
+        solutions[0] = self.deduce(|p| {
+            if p.pattern.len() != 6 {
+                return false;
+            }
+            let d = Digit::from(&p.pattern);
+            d != solutions[6] && d != solutions[9]
+        })?;
+        solutions[3] = self.deduce(|p| {
+            if p.pattern.len() != 5 {
+                return false;
+            }
+            let missing = p.missing();
+            solutions[1].sections[missing[0] as usize] == 0
+                && solutions[1].sections[missing[1] as usize] == 0
+        })?;
+        solutions[5] = self.deduce(|p| {
+            if p.pattern.len() != 5 {
+                return false;
+            }
+            let missing = solutions[6].missing();
+            !p.pattern.contains(&missing[0])
+        })?;
+        solutions[2] = self.deduce(|p| {
+            if p.pattern.len() != 5 {
+                return false;
+            }
+            let d = Digit::from(&p.pattern);
+            d != solutions[3] && d != solutions[5]
+        })?;
 
-        solutions
+        Ok(solutions)
     }
 
-    fn digits_to_num(&self, map: [Digit; 10]) -> u32 {
+    fn digits_to_num(&self, map: [Digit; 10]) -> Result<u32, Error> {
         let mut sum: u32 = 0;
 
-        if map.iter().filter(|v| **v == self.digits[0]).count() == 0 {
-            panic!("no match for {:?}", self.digits[0]);
+        if let Some(d) = self.digits.iter().find(|d| !map.contains(d)) {
+            return Err(Error::InvalidState(format!(
+                "output digit {:?} matches none of the patterns",
+                d.sections
+            )));
         }
         for (i, v) in map.iter().enumerate() {
             if *v == self.digits[0] {
@@ -264,84 +242,80 @@ impl Row {
             }
         }
 
-        sum
+        Ok(sum)
     }
 }
 
+fn segments(input: &str) -> IResult<&str, SignalPattern> {
+    map_res(alpha1, SignalPattern::try_from)(input)
+}
+
+fn digit(input: &str) -> IResult<&str, Digit> {
+    map_res(alpha1, Digit::try_from)(input)
+}
+
 fn my_u32(input: &str) -> IResult<&str, Row> {
-    let (rest, signal1) = alpha1(input)?;
+    let (rest, signal1) = segments(input)?;
     let (rest, _) = tag(" ")(rest)?;
-    let (rest, signal2) = alpha1(rest)?;
+    let (rest, signal2) = segments(rest)?;
     let (rest, _) = tag(" ")(rest)?;
-    let (rest, signal3) = alpha1(rest)?;
+    let (rest, signal3) = segments(rest)?;
     let (rest, _) = tag(" ")(rest)?;
-    let (rest, signal4) = alpha1(rest)?;
+    let (rest, signal4) = segments(rest)?;
     let (rest, _) = tag(" ")(rest)?;
-    let (rest, signal5) = alpha1(rest)?;
+    let (rest, signal5) = segments(rest)?;
     let (rest, _) = tag(" ")(rest)?;
-    let (rest, signal6) = alpha1(rest)?;
+    let (rest, signal6) = segments(rest)?;
     let (rest, _) = tag(" ")(rest)?;
-    let (rest, signal7) = alpha1(rest)?;
+    let (rest, signal7) = segments(rest)?;
     let (rest, _) = tag(" ")(rest)?;
-    let (rest, signal8) = alpha1(rest)?;
+    let (rest, signal8) = segments(rest)?;
     let (rest, _) = tag(" ")(rest)?;
-    let (rest, signal9) = alpha1(rest)?;
+    let (rest, signal9) = segments(rest)?;
     let (rest, _) = tag(" ")(rest)?;
-    let (rest, signal10) = alpha1(rest)?;
+    let (rest, signal10) = segments(rest)?;
     let (rest, _) = tag(" | ")(rest)?;
-    let (rest, digit1) = alpha1(rest)?;
+    let (rest, digit1) = digit(rest)?;
     let (rest, _) = tag(" ")(rest)?;
-    let (rest, digit2) = alpha1(rest)?;
+    let (rest, digit2) = digit(rest)?;
     let (rest, _) = tag(" ")(rest)?;
-    let (rest, digit3) = alpha1(rest)?;
+    let (rest, digit3) = digit(rest)?;
     let (rest, _) = tag(" ")(rest)?;
-    let (rest, digit4) = alpha1(rest)?;
+    let (rest, digit4) = digit(rest)?;
     let (rest, _) = tag("\n")(rest)?;
 
     Ok((
         rest,
         Row {
             patterns: [
-                SignalPattern::from(signal1),
-                SignalPattern::from(signal2),
-                SignalPattern::from(signal3),
-                SignalPattern::from(signal4),
-                SignalPattern::from(signal5),
-                SignalPattern::from(signal6),
-                SignalPattern::from(signal7),
-                SignalPattern::from(signal8),
-                SignalPattern::from(signal9),
-                SignalPattern::from(signal10),
-            ],
-            digits: [
-                Digit::from(digit1),
-                Digit::from(digit2),
-                Digit::from(digit3),
-                Digit::from(digit4),
+                signal1, signal2, signal3, signal4, signal5, signal6, signal7, signal8, signal9,
+                signal10,
             ],
+            digits: [digit1, digit2, digit3, digit4],
         },
     ))
 }
 
 fn multi(i: &str) -> IResult<&str, Vec<Row>> {
-    many0(my_u32)(i)
+    many_to_end(my_u32)(i)
 }
 
-fn parse_input(input: &str) -> Result<Vec<Row>, Error> {
-    let (_, data) = multi(input)?;
-
-    Ok(data)
+pub fn parse_input(input: &str) -> Result<Vec<Row>, Error> {
+    finish(input, multi)
 }
 
-fn part1(input: &[Row]) -> Result<String, Error> {
+pub fn part1(input: &[Row]) -> Result<String, Error> {
     Ok(format!(
         "{}",
         input.iter().map(|r| r.num_unique_digits()).sum::<u32>()
     ))
 }
 
-fn part2(input: &[Row]) -> Result<String, Error> {
-    let sum: u32 = input.iter().map(|r| r.digits_to_num(r.map())).sum();
+pub fn part2(input: &[Row]) -> Result<String, Error> {
+    let mut sum: u32 = 0;
+    for r in input {
+        sum += r.digits_to_num(r.map()?)?;
+    }
 
     Ok(format!("{}", sum))
 }
@@ -1,48 +1,47 @@
+use crate::parse::{finish, many_to_end, rectangular, tag, IResult};
+use crate::solver::Solver;
 use crate::Error;
-use nom::bytes::complete::tag;
 use nom::character::complete::digit1;
-use nom::multi::many0;
-use nom::IResult;
-use std::fs::read_to_string;
 
-pub fn calculate() -> Result<(String, String), Error> {
-    let input = parse_input(&read_to_string("input/day9")?)?;
+pub struct Day9;
 
-    Ok((part1(&input)?, part2(&input)?))
+impl Solver for Day9 {
+    type Input = Vec<Vec<u8>>;
+
+    fn day(&self) -> u8 {
+        9
+    }
+
+    fn name(&self) -> &'static str {
+        "Smoke Basin"
+    }
+
+    fn parse(&self, input: &str) -> Result<Self::Input, Error> {
+        parse_input(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Result<String, Error> {
+        part1(input)
+    }
+
+    fn part2(&self, input: &Self::Input) -> Result<String, Error> {
+        part2(input)
+    }
 }
 
 fn my_u32(input: &str) -> IResult<&str, Vec<u8>> {
     let (rest, data) = digit1(input)?;
     let (rest, _) = tag("\n")(rest)?;
 
-    Ok((
-        rest,
-        data.chars()
-            .map(|d| match d {
-                '0' => 0,
-                '1' => 1,
-                '2' => 2,
-                '3' => 3,
-                '4' => 4,
-                '5' => 5,
-                '6' => 6,
-                '7' => 7,
-                '8' => 8,
-                '9' => 9,
-                _ => panic!("wrong input"),
-            })
-            .collect::<Vec<u8>>(),
-    ))
+    Ok((rest, data.bytes().map(|d| d - b'0').collect()))
 }
 
 fn multi(i: &str) -> IResult<&str, Vec<Vec<u8>>> {
-    many0(my_u32)(i)
+    many_to_end(my_u32)(i)
 }
 
-fn parse_input(input: &str) -> Result<Vec<Vec<u8>>, Error> {
-    let (_, data) = multi(input)?;
-
-    Ok(data)
+pub fn parse_input(input: &str) -> Result<Vec<Vec<u8>>, Error> {
+    rectangular(finish(input, multi)?)
 }
 
 fn find_minimum(input: &[Vec<u8>]) -> Vec<(usize, usize)> {
@@ -89,8 +88,7 @@ fn find_basin_size(x: usize, y: usize, input: &[Vec<u8>]) -> usize {
 
     to_check.push((x, y));
 
-    while !to_check.is_empty() {
-        let (x, y) = to_check.pop().unwrap();
+    while let Some((x, y)) = to_check.pop() {
         if !is_ridge(x, y, input) {
             included.push((x, y));
 
@@ -130,7 +128,7 @@ fn find_basin_size(x: usize, y: usize, input: &[Vec<u8>]) -> usize {
     included.len()
 }
 
-fn part1(input: &[Vec<u8>]) -> Result<String, Error> {
+pub fn part1(input: &[Vec<u8>]) -> Result<String, Error> {
     Ok(format!(
         "{}",
         find_minimum(input)
@@ -140,7 +138,7 @@ fn part1(input: &[Vec<u8>]) -> Result<String, Error> {
     ))
 }
 
-fn part2(input: &[Vec<u8>]) -> Result<String, Error> {
+pub fn part2(input: &[Vec<u8>]) -> Result<String, Error> {
     let mut res: Vec<usize> = find_minimum(input)
         .iter()
         .map(|(x, y)| find_basin_size(*x, *y, input))
@@ -188,7 +186,7 @@ mod tests {
 
     #[test]
     pub fn test_is_ridge() {
-        assert_eq!(true, is_ridge(0, 2, &input()));
+        assert!(is_ridge(0, 2, &input()));
     }
 
     #[test]
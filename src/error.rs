@@ -0,0 +1,126 @@
+use crate::parse::Location;
+use std::fmt;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The puzzle input doesn't follow the format of the day.
+    Parse(Location),
+    /// The input parsed, but describes something the puzzle can't work with.
+    InvalidState(String),
+    /// The puzzle has no answer for the input, e.g. no bingo board ever wins.
+    NoSolution(&'static str),
+    /// The input file for a day doesn't exist.
+    InputNotFound {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// There is no solver registered for the day.
+    UnsupportedDay(u8),
+    /// Invalid command line values, answers file or baseline file.
+    Config(String),
+    IO(std::io::Error),
+}
+
+impl Error {
+    /// Process exit code for the error category, 1 is reserved for wrong answers and
+    /// benchmark regressions.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Config(_) => 2,
+            Error::Parse(_) => 3,
+            Error::InvalidState(_) => 4,
+            Error::NoSolution(_) => 5,
+            Error::InputNotFound { .. } => 6,
+            Error::UnsupportedDay(_) => 7,
+            Error::IO(_) => 8,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse(location) => write!(f, "parse error at {}", location),
+            Error::InvalidState(msg) => write!(f, "invalid puzzle state: {}", msg),
+            Error::NoSolution(msg) => write!(f, "no solution: {}", msg),
+            Error::InputNotFound { path, .. } => {
+                write!(f, "input not found: {}", path.display())
+            }
+            Error::UnsupportedDay(day) => write!(f, "day {} is not implemented", day),
+            Error::Config(msg) => f.write_str(msg),
+            Error::IO(err) => write!(f, "I/O error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::InputNotFound { source, .. } => Some(source),
+            Error::IO(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::IO(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Error;
+    use crate::parse::Location;
+    use std::error::Error as _;
+    use std::io::ErrorKind;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            "day 30 is not implemented",
+            Error::UnsupportedDay(30).to_string()
+        );
+        assert_eq!(
+            "no solution: no winner",
+            Error::NoSolution("no winner").to_string()
+        );
+    }
+
+    #[test]
+    fn test_source() {
+        let err = Error::InputNotFound {
+            path: PathBuf::from("input/day30"),
+            source: ErrorKind::NotFound.into(),
+        };
+
+        assert_eq!("input not found: input/day30", err.to_string());
+        assert!(err.source().is_some());
+        assert!(Error::UnsupportedDay(30).source().is_none());
+    }
+
+    #[test]
+    fn test_exit_codes_are_distinct() {
+        let mut codes = vec![
+            Error::Parse(Location::new("", "", String::new())).exit_code(),
+            Error::InputNotFound {
+                path: PathBuf::new(),
+                source: ErrorKind::NotFound.into(),
+            }
+            .exit_code(),
+            Error::Config(String::new()).exit_code(),
+            Error::InvalidState(String::new()).exit_code(),
+            Error::NoSolution("").exit_code(),
+            Error::UnsupportedDay(0).exit_code(),
+            Error::IO(ErrorKind::Other.into()).exit_code(),
+        ];
+        codes.sort_unstable();
+        codes.dedup();
+
+        assert_eq!(7, codes.len());
+        assert!(!codes.contains(&1));
+    }
+}
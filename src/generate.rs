@@ -0,0 +1,718 @@
+use crate::{day11, day16, Error};
+use rand::seq::{index, SliceRandom};
+use rand::Rng;
+use rand_core::SeedableRng;
+use rand_pcg::Pcg64;
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::ops::{Bound, RangeBounds};
+
+/// Size knobs for the generated inputs, days fall back to roughly the size of a real
+/// puzzle input for anything that isn't set.
+#[derive(Default)]
+pub struct Size {
+    /// Number of items for the list based days: depths, commands, report lines, fish,
+    /// crabs, displays, navigation lines, dots and the polymer template length.
+    pub count: Option<usize>,
+    /// Grid width for days 9 and 15, bits per number for day 3 and the paper or ocean
+    /// floor width for days 5 and 13.
+    pub width: Option<usize>,
+    /// Grid height for days 9 and 15 and the paper or ocean floor height for days 5
+    /// and 13.
+    pub height: Option<usize>,
+    /// Number of bingo boards for day 4.
+    pub boards: Option<usize>,
+    /// Number of vent lines for day 5.
+    pub lines: Option<usize>,
+    /// Packet nesting depth for day 16.
+    pub depth: Option<usize>,
+    /// Number of caves besides start and end for day 12.
+    pub caves: Option<usize>,
+}
+
+fn knob(
+    value: Option<usize>,
+    default: usize,
+    range: impl RangeBounds<usize>,
+    name: &str,
+) -> Result<usize, Error> {
+    let value = value.unwrap_or(default);
+    if !range.contains(&value) {
+        let min = match range.start_bound() {
+            Bound::Included(min) => *min,
+            _ => 0,
+        };
+        return Err(Error::Config(match range.end_bound() {
+            Bound::Included(max) => format!(
+                "--{} must be between {} and {}, got {}",
+                name, min, max, value
+            ),
+            _ => format!("--{} must be at least {}, got {}", name, min, value),
+        }));
+    }
+
+    Ok(value)
+}
+
+/// Emits a random, valid puzzle input for `day`. The same seed and size always give
+/// the same input.
+pub fn generate(day: u8, seed: u64, size: &Size) -> Result<String, Error> {
+    let mut rng = Pcg64::seed_from_u64(seed);
+
+    match day {
+        1 => day1(&mut rng, knob(size.count, 2000, 0.., "count")?),
+        2 => day2(&mut rng, knob(size.count, 1000, 0.., "count")?),
+        3 => day3(
+            &mut rng,
+            knob(size.count, 1000, 1.., "count")?,
+            knob(size.width, 12, 1.., "width")?,
+        ),
+        4 => day4(&mut rng, knob(size.boards, 100, 1.., "boards")?),
+        5 => day5(
+            &mut rng,
+            knob(size.lines, 500, 0.., "lines")?,
+            knob(size.width, 1000, 2.., "width")?,
+            knob(size.height, 1000, 2.., "height")?,
+        ),
+        6 => day6(&mut rng, knob(size.count, 300, 1.., "count")?),
+        7 => day7(&mut rng, knob(size.count, 1000, 1.., "count")?),
+        8 => day8(&mut rng, knob(size.count, 200, 0.., "count")?),
+        9 => day9(
+            &mut rng,
+            knob(size.width, 100, 1.., "width")?,
+            knob(size.height, 100, 1.., "height")?,
+        ),
+        10 => day10(&mut rng, knob(size.count, 100, 1.., "count")?),
+        11 => {
+            let width = knob(size.width, 10, 1.., "width")?;
+            if width != knob(size.height, width, 1.., "height")? || width > day11::MAX_SIZE {
+                return Err(Error::Config(format!(
+                    "the day 11 grid has to be square and at most {} wide",
+                    day11::MAX_SIZE
+                )));
+            }
+            day11(&mut rng, width)
+        }
+        12 => day12(&mut rng, knob(size.caves, 10, 0.., "caves")?),
+        13 => day13(
+            &mut rng,
+            knob(size.count, 800, 0.., "count")?,
+            knob(size.width, 1311, 2.., "width")?,
+            knob(size.height, 895, 2.., "height")?,
+        ),
+        14 => day14(&mut rng, knob(size.count, 20, 1.., "count")?),
+        15 => {
+            let width = knob(size.width, 100, 1.., "width")?;
+            let height = knob(size.height, 100, 1.., "height")?;
+            Ok(render(&digits(&mut rng, width, height, 1)))
+        }
+        16 => day16(
+            &mut rng,
+            knob(size.depth, 6, 0..=day16::MAX_DEPTH - 1, "depth")?,
+        ),
+        _ => Err(Error::UnsupportedDay(day)),
+    }
+}
+
+fn day1(rng: &mut Pcg64, count: usize) -> Result<String, Error> {
+    let mut depth: u32 = rng.gen_range(100..200);
+    let mut out = String::new();
+    for _ in 0..count {
+        depth = depth.saturating_add_signed(rng.gen_range(-20..=40));
+        writeln!(out, "{}", depth).unwrap();
+    }
+
+    Ok(out)
+}
+
+fn day2(rng: &mut Pcg64, count: usize) -> Result<String, Error> {
+    let mut depth = 0;
+    let mut out = String::new();
+    for _ in 0..count {
+        let amount = rng.gen_range(1..=9);
+        let command = match rng.gen_range(0..3) {
+            0 => "forward",
+            1 if depth >= amount => {
+                depth -= amount;
+                "up"
+            }
+            _ => {
+                depth += amount;
+                "down"
+            }
+        };
+        writeln!(out, "{} {}", command, amount).unwrap();
+    }
+
+    Ok(out)
+}
+
+/// The life support rating filters the report down to a single number, so the numbers
+/// have to be distinct.
+fn day3(rng: &mut Pcg64, count: usize, width: usize) -> Result<String, Error> {
+    let max = usize::BITS as usize - 1;
+    if width > max {
+        return Err(Error::Config(format!(
+            "--width can be at most {} bits for day 3, got {}",
+            max, width
+        )));
+    }
+    let values = 1usize << width;
+    if count > values {
+        return Err(Error::Config(format!(
+            "--count can be at most {} for {} bit numbers",
+            values, width
+        )));
+    }
+
+    let mut out = String::new();
+    for n in index::sample(rng, values, count) {
+        writeln!(out, "{:0width$b}", n, width = width).unwrap();
+    }
+
+    Ok(out)
+}
+
+/// All numbers 0 to 99 are drawn, so every board wins eventually.
+fn day4(rng: &mut Pcg64, boards: usize) -> Result<String, Error> {
+    let mut draws: Vec<u8> = (0..100).collect();
+    draws.shuffle(rng);
+    let draws: Vec<String> = draws.iter().map(|n| n.to_string()).collect();
+
+    let mut out = draws.join(",");
+    out.push('\n');
+    for _ in 0..boards {
+        out.push('\n');
+        let numbers = index::sample(rng, 100, 25).into_vec();
+        for row in numbers.chunks(5) {
+            let row: Vec<String> = row.iter().map(|n| format!("{:>2}", n)).collect();
+            writeln!(out, "{}", row.join(" ")).unwrap();
+        }
+    }
+
+    Ok(out)
+}
+
+/// Horizontal, vertical and 45 degree lines inside a `width` by `height` floor.
+fn day5(rng: &mut Pcg64, lines: usize, width: usize, height: usize) -> Result<String, Error> {
+    let mut out = String::new();
+    for _ in 0..lines {
+        let (x1, y1) = (rng.gen_range(0..width), rng.gen_range(0..height));
+        let (x2, y2) = loop {
+            let end = match rng.gen_range(0..3) {
+                0 => (rng.gen_range(0..width), y1),
+                1 => (x1, rng.gen_range(0..height)),
+                _ => {
+                    let x2 = rng.gen_range(0..width);
+                    let d = x1.abs_diff(x2);
+                    if y1 + d < height {
+                        (x2, y1 + d)
+                    } else if y1 >= d {
+                        (x2, y1 - d)
+                    } else {
+                        continue;
+                    }
+                }
+            };
+            if end != (x1, y1) {
+                break end;
+            }
+        };
+        writeln!(out, "{},{} -> {},{}", x1, y1, x2, y2).unwrap();
+    }
+
+    Ok(out)
+}
+
+fn day6(rng: &mut Pcg64, count: usize) -> Result<String, Error> {
+    let timers: Vec<String> = (0..count)
+        .map(|_| rng.gen_range(1..=5).to_string())
+        .collect();
+
+    Ok(format!("{}\n", timers.join(",")))
+}
+
+fn day7(rng: &mut Pcg64, count: usize) -> Result<String, Error> {
+    let crabs: Vec<String> = (0..count)
+        .map(|_| rng.gen_range(0..2000).to_string())
+        .collect();
+
+    Ok(format!("{}\n", crabs.join(",")))
+}
+
+/// Segments lit for each digit on a correctly wired display.
+const DIGITS: [&str; 10] = [
+    "abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg", "abcdfg",
+];
+
+/// Every display gets its own wiring, the patterns are shuffled and so are the
+/// segments within each pattern.
+fn day8(rng: &mut Pcg64, count: usize) -> Result<String, Error> {
+    let mut out = String::new();
+    for _ in 0..count {
+        let mut wiring: Vec<char> = "abcdefg".chars().collect();
+        wiring.shuffle(rng);
+        let scramble = |digit: usize, rng: &mut Pcg64| {
+            let mut segments: Vec<char> = DIGITS[digit]
+                .bytes()
+                .map(|s| wiring[(s - b'a') as usize])
+                .collect();
+            segments.shuffle(rng);
+            segments.into_iter().collect::<String>()
+        };
+
+        let mut order: Vec<usize> = (0..10).collect();
+        order.shuffle(rng);
+        let patterns: Vec<String> = order.iter().map(|d| scramble(*d, rng)).collect();
+        let output: Vec<String> = (0..4)
+            .map(|_| {
+                let digit = rng.gen_range(0..10);
+                scramble(digit, rng)
+            })
+            .collect();
+        writeln!(out, "{} | {}", patterns.join(" "), output.join(" ")).unwrap();
+    }
+
+    Ok(out)
+}
+
+fn digits(rng: &mut Pcg64, width: usize, height: usize, min: u8) -> Vec<Vec<u8>> {
+    (0..height)
+        .map(|_| (0..width).map(|_| rng.gen_range(min..=9)).collect())
+        .collect()
+}
+
+fn render(grid: &[Vec<u8>]) -> String {
+    let mut out = String::new();
+    for row in grid {
+        out.extend(row.iter().map(|d| (b'0' + d) as char));
+        out.push('\n');
+    }
+
+    out
+}
+
+fn neighbours(cell: usize, width: usize, height: usize) -> impl Iterator<Item = usize> {
+    let (x, y) = (cell % width, cell / width);
+    [
+        (x > 0).then(|| cell - 1),
+        (x + 1 < width).then(|| cell + 1),
+        (y > 0).then(|| cell - width),
+        (y + 1 < height).then(|| cell + width),
+    ]
+    .into_iter()
+    .flatten()
+}
+
+/// Basins around random low points, separated by ridges of 9. Heights grow with the
+/// distance to the low point, so every basin has exactly one, like in a real input.
+fn day9(rng: &mut Pcg64, width: usize, height: usize) -> Result<String, Error> {
+    let cells = width * height;
+    let low_points = index::sample(rng, cells, (cells / 50).max(1)).into_vec();
+
+    let mut region = vec![usize::MAX; cells];
+    let mut queue: VecDeque<usize> = low_points.iter().copied().collect();
+    for (r, cell) in low_points.iter().enumerate() {
+        region[*cell] = r;
+    }
+    while let Some(cell) = queue.pop_front() {
+        for n in neighbours(cell, width, height) {
+            if region[n] == usize::MAX {
+                region[n] = region[cell];
+                queue.push_back(n);
+            }
+        }
+    }
+
+    // Only one side of a border becomes ridge, which keeps neighbouring basins apart.
+    let ridge: Vec<bool> = (0..cells)
+        .map(|cell| neighbours(cell, width, height).any(|n| region[n] < region[cell]))
+        .collect();
+
+    let mut heights = vec![9u8; cells];
+    let mut queue = VecDeque::new();
+    for cell in low_points.iter().filter(|cell| !ridge[**cell]) {
+        heights[*cell] = rng.gen_range(0..=3);
+        queue.push_back(*cell);
+    }
+    let mut seen: Vec<bool> = ridge.clone();
+    while let Some(cell) = queue.pop_front() {
+        seen[cell] = true;
+        for n in neighbours(cell, width, height) {
+            if !seen[n] {
+                seen[n] = true;
+                heights[n] = (heights[cell] + 1).min(8);
+                queue.push_back(n);
+            }
+        }
+    }
+
+    let grid: Vec<Vec<u8>> = heights.chunks(width).map(|row| row.to_vec()).collect();
+
+    Ok(render(&grid))
+}
+
+/// Builds a grid that synchronizes by construction, so it works for any size. Most cells
+/// share one value and flash together once it reaches 10. The others are up to 3 lower
+/// and never next to each other, so the flashes of their at least 3 neighbours carry
+/// them over 9 in the same step.
+fn day11(rng: &mut Pcg64, side: usize) -> Result<String, Error> {
+    let background: u8 = rng.gen_range(3..=9);
+    let mut grid = vec![vec![background; side]; side];
+
+    for y in 0..side {
+        for x in 0..side {
+            let lower_neighbour = (y.saturating_sub(1)..=y)
+                .flat_map(|ny| (x.saturating_sub(1)..(x + 2).min(side)).map(move |nx| (ny, nx)))
+                .any(|(ny, nx)| (ny, nx) < (y, x) && grid[ny][nx] != background);
+            if !lower_neighbour && rng.gen_bool(0.3) {
+                grid[y][x] = background - rng.gen_range(1..=3);
+            }
+        }
+    }
+
+    Ok(render(&grid))
+}
+
+const BRACKETS: [(char, char); 4] = [('(', ')'), ('[', ']'), ('{', '}'), ('<', '>')];
+
+/// Alternates between incomplete and corrupted lines, starting with an incomplete one.
+/// The open chunks are kept shallow so the completion scores fit in 64 bits.
+fn day10(rng: &mut Pcg64, count: usize) -> Result<String, Error> {
+    let mut out = String::new();
+    for i in 0..count {
+        let mut stack: Vec<usize> = vec![];
+        for _ in 0..rng.gen_range(20..110) {
+            if !stack.is_empty() && (stack.len() >= 20 || rng.gen_bool(0.45)) {
+                out.push(BRACKETS[stack.pop().unwrap()].1);
+            } else {
+                let b = rng.gen_range(0..BRACKETS.len());
+                stack.push(b);
+                out.push(BRACKETS[b].0);
+            }
+        }
+        if stack.is_empty() {
+            let b = rng.gen_range(0..BRACKETS.len());
+            stack.push(b);
+            out.push(BRACKETS[b].0);
+        }
+        if i % 2 == 1 {
+            let open = stack[stack.len() - 1];
+            let wrong = (open + rng.gen_range(1..BRACKETS.len())) % BRACKETS.len();
+            out.push(BRACKETS[wrong].1);
+        }
+        out.push('\n');
+    }
+
+    Ok(out)
+}
+
+/// Caves hang off a random spanning tree from start, so end is always reachable, with
+/// some extra passages on top. Two large caves are never connected.
+fn day12(rng: &mut Pcg64, caves: usize) -> Result<String, Error> {
+    if caves > 26 * 26 {
+        return Err(Error::Config(format!(
+            "--caves can be at most {}, got {}",
+            26 * 26,
+            caves
+        )));
+    }
+
+    let mut names: Vec<String> = index::sample(rng, 26 * 26, caves)
+        .into_iter()
+        .map(|i| {
+            let name: String = [b'a' + (i / 26) as u8, b'a' + (i % 26) as u8]
+                .iter()
+                .map(|c| *c as char)
+                .collect();
+            if rng.gen_bool(0.25) {
+                name.to_uppercase()
+            } else {
+                name
+            }
+        })
+        .collect();
+    names.insert(0, "start".to_owned());
+    names.push("end".to_owned());
+    let large = |n: &str| n.chars().all(|c| c.is_ascii_uppercase());
+
+    let mut passages = vec![];
+    for i in 1..names.len() {
+        let candidates: Vec<usize> = (0..i)
+            .filter(|j| !(large(&names[i]) && large(&names[*j])))
+            .collect();
+        passages.push((*candidates.choose(rng).unwrap(), i));
+    }
+    for _ in 0..caves / 2 {
+        let (a, b) = (rng.gen_range(0..names.len()), rng.gen_range(0..names.len()));
+        let (a, b) = (a.min(b), a.max(b));
+        if a != b && !(large(&names[a]) && large(&names[b])) && !passages.contains(&(a, b)) {
+            passages.push((a, b));
+        }
+    }
+    passages.shuffle(rng);
+
+    let mut out = String::new();
+    for (a, b) in passages {
+        let (a, b) = if rng.gen_bool(0.5) { (a, b) } else { (b, a) };
+        writeln!(out, "{}-{}", names[a], names[b]).unwrap();
+    }
+
+    Ok(out)
+}
+
+/// Folds the paper in half, alternating between x and y, until it is small enough to
+/// read. There is always at least one fold.
+fn day13(rng: &mut Pcg64, count: usize, width: usize, height: usize) -> Result<String, Error> {
+    let mut out = String::new();
+    for _ in 0..count {
+        writeln!(
+            out,
+            "{},{}",
+            rng.gen_range(0..width),
+            rng.gen_range(0..height)
+        )
+        .unwrap();
+    }
+    out.push('\n');
+
+    let (mut w, mut h) = (width, height);
+    let mut folds = 0;
+    while folds == 0 || w > 40 || h > 6 {
+        if folds == 0 || w > 40 {
+            w /= 2;
+            writeln!(out, "fold along x={}", w).unwrap();
+            folds += 1;
+        }
+        if h > 6 {
+            h /= 2;
+            writeln!(out, "fold along y={}", h).unwrap();
+            folds += 1;
+        }
+    }
+
+    Ok(out)
+}
+
+/// A template over ten elements, with an insertion rule for every pair.
+fn day14(rng: &mut Pcg64, count: usize) -> Result<String, Error> {
+    let elements: Vec<char> = "BCFHKNOPSV".chars().collect();
+
+    let mut out: String = (0..count).map(|_| *elements.choose(rng).unwrap()).collect();
+    out.push_str("\n\n");
+    for a in &elements {
+        for b in &elements {
+            writeln!(out, "{}{} -> {}", a, b, elements.choose(rng).unwrap()).unwrap();
+        }
+    }
+
+    Ok(out)
+}
+
+fn push_bits(bits: &mut Vec<bool>, value: u64, width: usize) {
+    bits.extend((0..width).rev().map(|i| value >> i & 1 == 1));
+}
+
+/// Appends a packet nested `depth` levels deep and returns its value. Operators that
+/// would overflow are swapped for ones that don't, so the whole transmission evaluates.
+fn packet(rng: &mut Pcg64, bits: &mut Vec<bool>, depth: usize) -> u64 {
+    push_bits(bits, rng.gen_range(0..8), 3);
+
+    if depth == 0 {
+        let value: u64 = rng.gen_range(0..10_000);
+        push_bits(bits, 4, 3);
+        let groups = (64 - value.leading_zeros() as usize).div_ceil(4).max(1);
+        for g in (0..groups).rev() {
+            bits.push(g > 0);
+            push_bits(bits, value >> (g * 4), 4);
+        }
+        return value;
+    }
+
+    let mut type_id = [0, 1, 2, 3, 5, 6, 7][rng.gen_range(0..7)];
+    let count = if type_id >= 5 {
+        2
+    } else {
+        rng.gen_range(1..=3)
+    };
+    let mut sub_bits = vec![];
+    let mut values = vec![];
+    for i in 0..count {
+        // Only the first sub-packet goes all the way down, so the size grows linearly
+        // with the depth.
+        let sub_depth = if i == 0 {
+            depth - 1
+        } else {
+            rng.gen_range(0..depth.min(2))
+        };
+        values.push(packet(rng, &mut sub_bits, sub_depth));
+    }
+
+    let product = values.iter().try_fold(1u64, |p, v| p.checked_mul(*v));
+    let sum = values.iter().try_fold(0u64, |s, v| s.checked_add(*v));
+    if type_id == 1 && product.is_none() {
+        type_id = 0;
+    }
+    if type_id == 0 && sum.is_none() {
+        type_id = 2;
+    }
+    let value = match type_id {
+        0 => sum.unwrap(),
+        1 => product.unwrap(),
+        2 => *values.iter().min().unwrap(),
+        3 => *values.iter().max().unwrap(),
+        5 => (values[0] > values[1]) as u64,
+        6 => (values[0] < values[1]) as u64,
+        _ => (values[0] == values[1]) as u64,
+    };
+
+    push_bits(bits, type_id, 3);
+    if sub_bits.len() < 1 << 15 && rng.gen_bool(0.5) {
+        bits.push(false);
+        push_bits(bits, sub_bits.len() as u64, 15);
+    } else {
+        bits.push(true);
+        push_bits(bits, count as u64, 11);
+    }
+    bits.extend(sub_bits);
+
+    value
+}
+
+fn day16(rng: &mut Pcg64, depth: usize) -> Result<String, Error> {
+    let mut bits = vec![];
+    packet(rng, &mut bits, depth);
+    bits.resize(bits.len().div_ceil(8) * 8, false);
+
+    let mut out: String = bits
+        .chunks(4)
+        .map(|nibble| {
+            let v = nibble.iter().fold(0, |v, b| v << 1 | *b as u32);
+            char::from_digit(v, 16).unwrap().to_ascii_uppercase()
+        })
+        .collect();
+    out.push('\n');
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day16;
+    use crate::generate::{generate, Size};
+    use crate::solver::registry;
+
+    fn small(day: u8) -> Size {
+        let side = if day == 11 { 10 } else { 20 };
+
+        Size {
+            count: Some(50),
+            width: Some(side),
+            height: Some(side),
+            boards: Some(5),
+            lines: Some(50),
+            depth: Some(4),
+            caves: Some(8),
+        }
+    }
+
+    #[test]
+    fn test_generated_inputs_solve() {
+        for runner in registry() {
+            for seed in 0..5 {
+                let input = generate(runner.day(), seed, &small(runner.day())).unwrap();
+                let parsed = runner.parse(&input).unwrap();
+
+                runner.part1(parsed.as_ref()).unwrap();
+                runner.part2(parsed.as_ref()).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_same_seed_same_input() {
+        let size = Size::default();
+
+        assert_eq!(
+            generate(9, 7, &size).unwrap(),
+            generate(9, 7, &size).unwrap()
+        );
+        assert_ne!(
+            generate(9, 7, &size).unwrap(),
+            generate(9, 8, &size).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_size_knobs() {
+        let size = Size {
+            boards: Some(3),
+            ..Size::default()
+        };
+        let bingo = crate::day4::parse_input(&generate(4, 0, &size).unwrap()).unwrap();
+        assert_eq!(3, bingo.1.len());
+
+        let size = Size {
+            width: Some(7),
+            height: Some(4),
+            ..Size::default()
+        };
+        let grid = crate::day15::parse_input(&generate(15, 0, &size).unwrap()).unwrap();
+        assert_eq!((4, 7), (grid.len(), grid[0].len()));
+    }
+
+    #[test]
+    fn test_day11_sizes() {
+        let size = |width, height| Size {
+            width,
+            height,
+            ..Size::default()
+        };
+        let grid = generate(11, 0, &size(Some(5), None)).unwrap();
+
+        assert_eq!(5, crate::day11::parse_input_sized(&grid, 5).unwrap().len());
+        for seed in 0..5 {
+            let grid = generate(11, seed, &size(Some(200), None)).unwrap();
+            let grid = crate::day11::parse_input_sized(&grid, 200).unwrap();
+            assert!(crate::day11::first_sync(&grid, 10).is_some());
+        }
+        assert!(generate(11, 0, &size(Some(5), Some(6))).is_err());
+        assert!(generate(11, 0, &size(Some(1001), Some(1001))).is_err());
+    }
+
+    #[test]
+    fn test_day16_depth() {
+        let depth = |depth| Size {
+            depth: Some(depth),
+            ..Size::default()
+        };
+        let deepest = generate(16, 0, &depth(day16::MAX_DEPTH - 1)).unwrap();
+        let runner = crate::solver::find(16).unwrap();
+        let parsed = runner.parse(&deepest).unwrap();
+
+        assert!(runner.part2(parsed.as_ref()).is_ok());
+        assert!(deepest.len() < 10_000);
+        assert!(generate(16, 0, &depth(day16::MAX_DEPTH)).is_err());
+    }
+
+    #[test]
+    fn test_invalid_sizes() {
+        let size = Size {
+            count: Some(5),
+            width: Some(2),
+            ..Size::default()
+        };
+
+        assert!(generate(3, 0, &size).is_err());
+        assert!(generate(
+            11,
+            0,
+            &Size {
+                height: Some(3),
+                ..size
+            }
+        )
+        .is_err());
+        assert!(generate(30, 0, &size).is_err());
+    }
+}
@@ -0,0 +1,99 @@
+use crate::Error;
+use std::fs::{read_to_string, File};
+use std::io::{stdin, BufRead, BufReader, ErrorKind, Read};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+fn not_found(path: &Path) -> impl FnOnce(std::io::Error) -> Error + '_ {
+    move |source| match source.kind() {
+        ErrorKind::NotFound => Error::InputNotFound {
+            path: path.to_owned(),
+            source,
+        },
+        _ => Error::IO(source),
+    }
+}
+
+fn read_file(path: &Path) -> Result<String, Error> {
+    read_to_string(path).map_err(not_found(path))
+}
+
+fn open_file(path: &Path) -> Result<Box<dyn BufRead>, Error> {
+    let file = File::open(path).map_err(not_found(path))?;
+
+    Ok(Box::new(BufReader::new(file)))
+}
+
+/// Calls `f` with the number and text of every line in `reader`, including the line
+/// ending, only one line is held in memory at a time.
+pub fn for_each_line(
+    reader: &mut dyn BufRead,
+    mut f: impl FnMut(usize, &str) -> Result<(), Error>,
+) -> Result<(), Error> {
+    let mut line = String::new();
+    let mut number = 0;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        number += 1;
+        f(number, &line)?;
+    }
+}
+
+/// Where puzzle input is read from.
+pub enum InputSource {
+    Stdin,
+    Path(PathBuf),
+}
+
+impl FromStr for InputSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "-" => InputSource::Stdin,
+            _ => InputSource::Path(PathBuf::from(s)),
+        })
+    }
+}
+
+impl InputSource {
+    /// Stdin and plain files hold the input for exactly one day, a directory holds a
+    /// `dayN` file per day.
+    pub fn is_single_day(&self) -> bool {
+        match self {
+            InputSource::Stdin => true,
+            InputSource::Path(path) => !path.is_dir(),
+        }
+    }
+
+    /// The file holding the input for `day`, `None` for stdin.
+    pub fn file(&self, day: u8) -> Option<PathBuf> {
+        match self {
+            InputSource::Stdin => None,
+            InputSource::Path(path) if path.is_dir() => Some(path.join(format!("day{}", day))),
+            InputSource::Path(path) => Some(path.clone()),
+        }
+    }
+
+    pub fn read(&self, day: u8) -> Result<String, Error> {
+        match self.file(day) {
+            None => {
+                let mut input = String::new();
+                stdin().read_to_string(&mut input)?;
+                Ok(input)
+            }
+            Some(path) => read_file(&path),
+        }
+    }
+
+    /// Like `read`, but hands out a reader so the input doesn't have to fit in memory.
+    pub fn open(&self, day: u8) -> Result<Box<dyn BufRead>, Error> {
+        match self.file(day) {
+            None => Ok(Box::new(stdin().lock())),
+            Some(path) => open_file(&path),
+        }
+    }
+}
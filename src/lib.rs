@@ -0,0 +1,34 @@
+//! Solutions for Advent of Code 2021.
+//!
+//! Every day lives in its own module with a public `parse_input`, `part1` and
+//! `part2`, plus the types its input parses into. The [`solver`] module ties the days
+//! together behind a common trait, which is what the command line tool runs.
+
+pub mod answers;
+pub mod bench;
+pub mod day1;
+pub mod day10;
+pub mod day11;
+pub mod day12;
+pub mod day13;
+pub mod day14;
+pub mod day15;
+pub mod day16;
+pub mod day2;
+pub mod day3;
+pub mod day4;
+pub mod day5;
+pub mod day6;
+pub mod day7;
+pub mod day8;
+pub mod day9;
+pub mod error;
+pub mod generate;
+pub mod input;
+pub mod output;
+pub mod parse;
+pub mod solver;
+#[cfg(all(feature = "watch", target_os = "linux"))]
+pub mod watch;
+
+pub use crate::error::Error;
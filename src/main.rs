@@ -1,83 +1,468 @@
-use clap::Parser;
-use nom::Err;
-
-mod day1;
-mod day10;
-mod day11;
-mod day12;
-mod day13;
-mod day14;
-mod day15;
-mod day16;
-mod day2;
-mod day3;
-mod day4;
-mod day5;
-mod day6;
-mod day7;
-mod day8;
-mod day9;
-
-#[derive(Debug)]
-pub enum Error {
-    Generic(&'static str),
-    GenericDyn(String),
-    IO(std::io::Error),
-}
+use adventofcode2021::answers::{Answers, Verdict};
+use adventofcode2021::generate::{self, Size};
+use adventofcode2021::input::InputSource;
+use adventofcode2021::output::{self, Format, Outcome};
+use adventofcode2021::solver::Param;
+#[cfg(all(feature = "watch", target_os = "linux"))]
+use adventofcode2021::watch;
+use adventofcode2021::{bench, solver, Error};
+use clap::{AppSettings, Parser, Subcommand};
+use std::env;
+use std::path::PathBuf;
+use std::process::exit;
+use std::str::FromStr;
+use std::thread;
+
+struct Days(Vec<u8>);
+
+impl FromStr for Days {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut days = vec![];
+        for part in s.split(',') {
+            let part = part.trim();
+            let parse = |v: &str| {
+                v.trim()
+                    .parse::<u8>()
+                    .map_err(|e| format!("invalid day '{}': {}", v, e))
+            };
+            match part.split_once('-') {
+                Some((from, to)) => {
+                    let (from, to) = (parse(from)?, parse(to)?);
+                    if from > to {
+                        return Err(format!("invalid day range '{}'", part));
+                    }
+                    days.extend(from..=to);
+                }
+                None => days.push(parse(part)?),
+            }
+        }
+        days.sort_unstable();
+        days.dedup();
 
-impl From<std::io::Error> for Error {
-    fn from(err: std::io::Error) -> Self {
-        Error::IO(err)
+        Ok(Days(days))
     }
 }
 
-impl From<nom::Err<nom::error::Error<&str>>> for Error {
-    fn from(err: Err<nom::error::Error<&str>>) -> Self {
-        Error::GenericDyn(format!("{:?}", err))
+enum Part {
+    One,
+    Two,
+    Both,
+}
+
+impl FromStr for Part {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1" => Ok(Part::One),
+            "2" => Ok(Part::Two),
+            "both" => Ok(Part::Both),
+            _ => Err(format!("unknown part '{}', expected 1, 2 or both", s)),
+        }
     }
 }
 
-impl From<std::num::ParseIntError> for Error {
-    fn from(err: std::num::ParseIntError) -> Self {
-        Error::GenericDyn(format!("{:?}", err))
+impl Part {
+    fn parts(&self) -> &'static [u8] {
+        match self {
+            Part::One => &[1],
+            Part::Two => &[2],
+            Part::Both => &[1, 2],
+        }
     }
 }
 
+#[derive(Subcommand)]
+enum Command {
+    /// Time parsing and each part of the selected days
+    Bench {
+        /// Days to benchmark, e.g. 3,7,12-16, defaults to all days
+        #[clap(long)]
+        days: Option<Days>,
+        /// Number of times each stage is run
+        #[clap(short = 'n', long, default_value = "10")]
+        iterations: usize,
+        /// Directory of dayN input files
+        #[clap(short, long, default_value = "input")]
+        input: InputSource,
+        /// Write the median timings to this file
+        #[clap(long)]
+        save_baseline: Option<PathBuf>,
+        /// Compare against a previously saved baseline file
+        #[clap(long)]
+        baseline: Option<PathBuf>,
+        /// Slowdown in percent over the baseline that counts as a regression
+        #[clap(long, default_value = "10")]
+        threshold: f64,
+    },
+    /// Print a random puzzle input for a day
+    Generate {
+        /// Day to generate the input for
+        #[clap(short, long)]
+        day: u8,
+        /// Seed for the random generator, the same seed gives the same input
+        #[clap(long, default_value = "0")]
+        seed: u64,
+        /// Number of items for list based days, e.g. depths, commands or crabs
+        #[clap(long)]
+        count: Option<usize>,
+        /// Grid or paper width, or the number of bits for day 3
+        #[clap(long)]
+        width: Option<usize>,
+        /// Grid or paper height
+        #[clap(long)]
+        height: Option<usize>,
+        /// Number of bingo boards for day 4
+        #[clap(long)]
+        boards: Option<usize>,
+        /// Number of vent lines for day 5
+        #[clap(long)]
+        lines: Option<usize>,
+        /// Packet nesting depth for day 16
+        #[clap(long)]
+        depth: Option<usize>,
+        /// Number of caves besides start and end for day 12
+        #[clap(long)]
+        caves: Option<usize>,
+    },
+}
+
 #[derive(Parser)]
 #[clap(
     version = "1.0",
-    author = "Alexander Kjäll <alexander.kjall@gmail.com>"
+    author = "Alexander Kjäll <alexander.kjall@gmail.com>",
+    setting = AppSettings::SubcommandsNegateReqs,
+    after_help = "EXIT CODES:
+    0  success
+    1  wrong answer or benchmark regression
+    2  invalid arguments, answers file or baseline
+    3  malformed puzzle input
+    4  invalid puzzle state
+    5  no solution for the input
+    6  input file not found
+    7  day not implemented
+    8  I/O error"
 )]
 struct Arguments {
-    #[clap(short, long)]
-    day: u8,
+    #[clap(subcommand)]
+    command: Option<Command>,
+    #[clap(short, long, required_unless_present_any = &["all", "days", "list"])]
+    day: Option<u8>,
+    /// Run every implemented day
+    #[clap(long, conflicts_with_all = &["day", "days"])]
+    all: bool,
+    /// Days to run, e.g. 3,7,12-16
+    #[clap(long, conflicts_with = "day")]
+    days: Option<Days>,
+    /// List the implemented days
+    #[clap(long)]
+    list: bool,
+    /// Puzzle input: a file, a directory of dayN files, or - for stdin
+    #[clap(short, long, default_value = "input")]
+    input: InputSource,
+    /// Which part to run: 1, 2 or both
+    #[clap(short, long, default_value = "both")]
+    part: Part,
+    /// Output format: text, json or csv
+    #[clap(short, long, default_value = "text")]
+    format: Format,
+    /// Check the answers against the answers file
+    #[clap(long)]
+    verify: bool,
+    /// Store the answers in the answers file
+    #[clap(long)]
+    record: bool,
+    /// File with the known answers
+    #[clap(long, default_value = "answers.toml")]
+    answers: PathBuf,
+    /// Override a puzzle constant of the selected days, e.g. --param steps=500
+    #[clap(
+        long = "param",
+        value_name = "KEY=VALUE",
+        multiple_occurrences(true),
+        number_of_values = 1,
+        conflicts_with_all = &["verify", "record"]
+    )]
+    params: Vec<Param>,
+    /// Read the input line by line in bounded memory, only days 1, 2, 5 and 10
+    #[clap(long)]
+    stream: bool,
+    /// Run the day again every time its input changes, Linux only
+    #[clap(
+        long,
+        requires = "day",
+        conflicts_with_all = &["verify", "record", "stream"]
+    )]
+    watch: bool,
+    /// Print an analysis of the day's input instead of the answers
+    #[clap(
+        long,
+        requires = "day",
+        conflicts_with_all = &["verify", "record", "stream", "watch"]
+    )]
+    report: bool,
+    /// Draw the day's input as a chart instead of printing the answers
+    #[clap(
+        long,
+        requires = "day",
+        conflicts_with_all = &["verify", "record", "stream", "watch", "report"]
+    )]
+    plot: bool,
+    /// Width of the chart, defaults to $COLUMNS or 80
+    #[clap(long, value_name = "COLUMNS", requires = "plot")]
+    plot_width: Option<usize>,
+    /// Print the states behind the day's answers in the chosen format, e.g. the route
+    /// of the submarine
+    #[clap(
+        long,
+        requires = "day",
+        conflicts_with_all = &["verify", "record", "stream", "watch", "report", "plot"]
+    )]
+    export: bool,
+    /// Number of threads to run days and parts on, 0 for one per CPU
+    #[clap(short, long, default_value = "1", conflicts_with_all = &["stream", "watch"])]
+    jobs: usize,
+    /// An example input to run and watch next to the real one
+    #[clap(long, requires = "watch")]
+    example: Option<PathBuf>,
+}
+
+#[cfg(all(feature = "watch", target_os = "linux"))]
+fn watch(day: u8, args: &Arguments) -> Result<(), Error> {
+    watch::watch(
+        day,
+        &args.input,
+        args.example.as_deref(),
+        args.part.parts(),
+        &args.params,
+    )
+}
+
+#[cfg(not(all(feature = "watch", target_os = "linux")))]
+fn watch(_day: u8, _args: &Arguments) -> Result<(), Error> {
+    Err(Error::Config(
+        "--watch needs a Linux build with the watch feature".to_owned(),
+    ))
 }
 
 fn main() {
     let args: Arguments = Arguments::parse();
 
-    let res = match args.day {
-        1 => day1::calculate(),
-        2 => day2::calculate(),
-        3 => day3::calculate(),
-        4 => day4::calculate(),
-        5 => day5::calculate(),
-        6 => day6::calculate(),
-        7 => day7::calculate(),
-        8 => day8::calculate(),
-        9 => day9::calculate(),
-        10 => day10::calculate(),
-        11 => day11::calculate(),
-        12 => day12::calculate(),
-        13 => day13::calculate(),
-        14 => day14::calculate(),
-        15 => day15::calculate(),
-        16 => day16::calculate(),
-        _ => Err(Error::Generic("illegal day")),
+    if let Some(Command::Bench {
+        days,
+        iterations,
+        input,
+        save_baseline,
+        baseline,
+        threshold,
+    }) = args.command
+    {
+        let days = match days {
+            Some(Days(days)) => days,
+            None => solver::registry().iter().map(|r| r.day()).collect(),
+        };
+        match bench::run(
+            &days,
+            &input,
+            iterations,
+            save_baseline.as_deref(),
+            baseline.as_deref(),
+            threshold,
+        ) {
+            Ok(0) => return,
+            Ok(_) => exit(1),
+            Err(err) => {
+                eprintln!("{}", err);
+                exit(err.exit_code());
+            }
+        }
+    }
+
+    if let Some(Command::Generate {
+        day,
+        seed,
+        count,
+        width,
+        height,
+        boards,
+        lines,
+        depth,
+        caves,
+    }) = args.command
+    {
+        let size = Size {
+            count,
+            width,
+            height,
+            boards,
+            lines,
+            depth,
+            caves,
+        };
+        match generate::generate(day, seed, &size) {
+            Ok(input) => print!("{}", input),
+            Err(err) => {
+                eprintln!("{}", err);
+                exit(err.exit_code());
+            }
+        }
+        return;
+    }
+
+    if args.list {
+        for runner in solver::registry() {
+            println!("{:>2} {}", runner.day(), runner.name());
+        }
+        return;
+    }
+
+    if let (true, Some(day)) = (args.watch, args.day) {
+        if let Err(err) = watch(day, &args) {
+            eprintln!("{}", err);
+            exit(err.exit_code());
+        }
+        return;
+    }
+
+    if let (true, Some(day)) = (args.report, args.day) {
+        match solver::report(day, &args.input, &args.params) {
+            Ok(report) => print!("{}", report),
+            Err(err) => {
+                eprintln!("{}", err);
+                exit(err.exit_code());
+            }
+        }
+        return;
+    }
+
+    if let (true, Some(day)) = (args.plot, args.day) {
+        let width = args
+            .plot_width
+            .or_else(|| env::var("COLUMNS").ok()?.parse().ok())
+            .unwrap_or(80);
+        match solver::plot(day, &args.input, &args.params, width) {
+            Ok(chart) => print!("{}", chart),
+            Err(err) => {
+                eprintln!("{}", err);
+                exit(err.exit_code());
+            }
+        }
+        return;
+    }
+
+    if let (true, Some(day)) = (args.export, args.day) {
+        let parts = args.part.parts();
+        match solver::export(day, &args.input, parts, &args.params, &args.format) {
+            Ok(export) => print!("{}", export),
+            Err(err) => {
+                eprintln!("{}", err);
+                exit(err.exit_code());
+            }
+        }
+        return;
+    }
+
+    let days = match (args.day, args.days) {
+        (Some(day), _) => vec![day],
+        (None, Some(Days(days))) => days,
+        (None, None) => solver::registry().iter().map(|r| r.day()).collect(),
+    };
+
+    if days.len() > 1 && args.input.is_single_day() {
+        eprintln!("--input must be a directory when running more than one day");
+        exit(2);
+    }
+
+    let mut known = if args.verify || args.record {
+        match Answers::load(&args.answers) {
+            Ok(known) => known,
+            Err(err) => {
+                eprintln!("{}", err);
+                exit(err.exit_code());
+            }
+        }
+    } else {
+        Answers::default()
     };
 
-    match res {
-        Ok((part1, part2)) => println!("day {}\npart 1: {}\npart 2: {}", args.day, part1, part2),
-        Err(err) => println!("{:?}", err),
+    let parts = args.part.parts();
+    let mut outcomes: Vec<Outcome> = if args.stream {
+        days.iter()
+            .map(|d| {
+                let params = solver::params_for(*d, &days, &args.params);
+                solver::stream(*d, &args.input, parts, &params)
+            })
+            .collect()
+    } else {
+        let jobs = match args.jobs {
+            0 => thread::available_parallelism().map_or(1, |n| n.get()),
+            jobs => jobs,
+        };
+        solver::run_all(&days, &args.input, parts, &args.params, jobs)
+    };
+
+    let mut mismatch = false;
+    if args.verify {
+        for o in &mut outcomes {
+            for a in o.result.iter_mut().flatten() {
+                if let Ok(answer) = &a.result {
+                    let verdict = known.check(o.day, a.part, answer);
+                    mismatch |= verdict == Verdict::Fail;
+                    a.verdict = Some(verdict);
+                }
+            }
+        }
+    }
+
+    output::print(&args.format, &outcomes, parts, args.day.is_none());
+
+    if args.record {
+        for o in &outcomes {
+            for a in o.result.iter().flatten() {
+                if let Ok(answer) = &a.result {
+                    known.set(o.day, a.part, answer);
+                }
+            }
+        }
+        if let Err(err) = known.save(&args.answers) {
+            eprintln!("{}", err);
+            exit(err.exit_code());
+        }
+    }
+
+    if let Some(err) = outcomes.iter().find_map(Outcome::error) {
+        exit(err.exit_code());
+    }
+    if mismatch {
+        exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Days;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_days_list_and_ranges() {
+        let Days(days) = Days::from_str("3,7,12-16").unwrap();
+
+        assert_eq!(vec![3, 7, 12, 13, 14, 15, 16], days);
+    }
+
+    #[test]
+    fn test_days_deduplicated() {
+        let Days(days) = Days::from_str("5,1-3,2").unwrap();
+
+        assert_eq!(vec![1, 2, 3, 5], days);
+    }
+
+    #[test]
+    fn test_days_invalid() {
+        assert!(Days::from_str("3-1").is_err());
+        assert!(Days::from_str("x").is_err());
+        assert!(Days::from_str("1,,2").is_err());
     }
 }
@@ -0,0 +1,368 @@
+use crate::answers::Verdict;
+use crate::Error;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// The result of running one part of a day.
+pub struct Answer {
+    pub part: u8,
+    pub result: Result<String, Error>,
+    pub elapsed: Duration,
+    /// Set when the answer has been checked against the known answers.
+    pub verdict: Option<Verdict>,
+}
+
+/// Everything that happened when running one day. `result` is an error when the day
+/// failed before any part could run, e.g. because the input could not be parsed.
+pub struct Outcome {
+    pub day: u8,
+    pub result: Result<Vec<Answer>, Error>,
+    pub elapsed: Duration,
+}
+
+impl Outcome {
+    fn answer(&self, part: u8) -> Option<&Answer> {
+        match &self.result {
+            Ok(answers) => answers.iter().find(|a| a.part == part),
+            Err(_) => None,
+        }
+    }
+
+    fn verdicts(&self, parts: &[u8]) -> Option<String> {
+        let verdicts: Vec<String> = parts
+            .iter()
+            .filter_map(|p| self.answer(*p).and_then(|a| a.verdict))
+            .map(|v| v.to_string())
+            .collect();
+
+        if verdicts.is_empty() {
+            None
+        } else {
+            Some(verdicts.join(" "))
+        }
+    }
+
+    /// The first error of the day, if any.
+    pub fn error(&self) -> Option<&Error> {
+        match &self.result {
+            Ok(answers) => answers.iter().find_map(|a| a.result.as_ref().err()),
+            Err(err) => Some(err),
+        }
+    }
+
+    fn status(&self) -> String {
+        match self.error() {
+            None => "ok".to_owned(),
+            Some(err) => format!("failed: {}", err),
+        }
+    }
+}
+
+pub enum Format {
+    Text,
+    Json,
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            _ => Err(format!(
+                "unknown format '{}', expected text, json or csv",
+                s
+            )),
+        }
+    }
+}
+
+/// One flattened (day, part) line, as used by the machine readable formats.
+struct Record {
+    day: u8,
+    part: u8,
+    answer: Option<String>,
+    time_ms: f64,
+    status: String,
+    check: Option<Verdict>,
+}
+
+fn records(outcomes: &[Outcome], parts: &[u8]) -> Vec<Record> {
+    let mut records = vec![];
+    for o in outcomes {
+        for part in parts {
+            records.push(match o.answer(*part) {
+                Some(a) => Record {
+                    day: o.day,
+                    part: *part,
+                    answer: a.result.as_ref().ok().cloned(),
+                    time_ms: millis(a.elapsed),
+                    status: match &a.result {
+                        Ok(_) => "ok".to_owned(),
+                        Err(err) => format!("failed: {}", err),
+                    },
+                    check: a.verdict,
+                },
+                None => Record {
+                    day: o.day,
+                    part: *part,
+                    answer: None,
+                    time_ms: 0.0,
+                    status: o.status(),
+                    check: None,
+                },
+            });
+        }
+    }
+    records
+}
+
+fn millis(d: Duration) -> f64 {
+    d.as_secs_f64() * 1000.0
+}
+
+/// Prints the outcomes, `table` selects the summary table over plain text when the
+/// text format is used.
+pub fn print(format: &Format, outcomes: &[Outcome], parts: &[u8], table: bool) {
+    match format {
+        Format::Text if table => print_table(outcomes, parts),
+        Format::Text => outcomes.iter().for_each(print_text),
+        Format::Json => println!("{}", to_json(&records(outcomes, parts))),
+        Format::Csv => print!("{}", to_csv(&records(outcomes, parts))),
+    }
+}
+
+fn print_text(outcome: &Outcome) {
+    match &outcome.result {
+        Ok(answers) => {
+            println!("day {}", outcome.day);
+            for a in answers {
+                let label = match a.verdict {
+                    Some(verdict) => format!("part {} [{}]", a.part, verdict),
+                    None => format!("part {}", a.part),
+                };
+                match &a.result {
+                    Ok(answer) => println!("{}: {}", label, answer),
+                    Err(err) => println!("{}: {}", label, err),
+                }
+            }
+        }
+        Err(err) => println!("{}", err),
+    }
+}
+
+fn print_table(outcomes: &[Outcome], parts: &[u8]) {
+    let mut header = vec!["day".to_owned()];
+    header.extend(parts.iter().map(|p| format!("part {}", p)));
+    header.extend(["time".to_owned(), "status".to_owned()]);
+    let checked = outcomes.iter().any(|o| o.verdicts(parts).is_some());
+    if checked {
+        header.push("check".to_owned());
+    }
+
+    let mut rows = vec![];
+    let mut multiline = vec![];
+    let mut errors = vec![];
+
+    for o in outcomes {
+        let mut row = vec![o.day.to_string()];
+        for part in parts {
+            row.push(match o.answer(*part).map(|a| &a.result) {
+                Some(Ok(answer)) if answer.contains('\n') => {
+                    multiline.push((o.day, *part, answer.to_owned()));
+                    "(see below)".to_owned()
+                }
+                Some(Ok(answer)) => answer.to_owned(),
+                _ => "-".to_owned(),
+            });
+        }
+        row.push(format!("{:.3}ms", millis(o.elapsed)));
+        let status = o.status();
+        match status.split_once('\n') {
+            Some((first, _)) => {
+                row.push(first.to_owned());
+                errors.push((o.day, status.to_owned()));
+            }
+            None => row.push(status),
+        }
+        if checked {
+            row.push(o.verdicts(parts).unwrap_or_default());
+        }
+        rows.push(row);
+    }
+
+    let mut widths: Vec<usize> = header.iter().map(String::len).collect();
+    for row in &rows {
+        for (i, cell) in row.iter().enumerate() {
+            widths[i] = widths[i].max(cell.len());
+        }
+    }
+
+    let line = |cells: &[String]| {
+        let cells: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(c, w)| format!("{:<w$}", c, w = w))
+            .collect();
+        println!("{}", cells.join(" | ").trim_end());
+    };
+
+    line(&header);
+    let separator: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+    println!("{}", separator.join("-+-").trim_end());
+    for row in &rows {
+        line(row);
+    }
+
+    for (day, part, answer) in multiline {
+        println!(
+            "\nday {} part {}:\n{}",
+            day,
+            part,
+            answer.trim_matches('\n')
+        );
+    }
+
+    for (day, status) in errors {
+        println!("\nday {} {}", day, status);
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn to_json(records: &[Record]) -> String {
+    let objects: Vec<String> = records
+        .iter()
+        .map(|r| {
+            format!(
+                "{{\"day\":{},\"part\":{},\"answer\":{},\"time_ms\":{:.3},\"status\":{},\"check\":{}}}",
+                r.day,
+                r.part,
+                r.answer
+                    .as_deref()
+                    .map(json_string)
+                    .unwrap_or_else(|| "null".to_owned()),
+                r.time_ms,
+                json_string(&r.status),
+                r.check
+                    .map(|c| json_string(&c.to_string()))
+                    .unwrap_or_else(|| "null".to_owned())
+            )
+        })
+        .collect();
+
+    format!("[{}]", objects.join(","))
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_owned()
+    }
+}
+
+fn to_csv(records: &[Record]) -> String {
+    let mut out = "day,part,answer,time_ms,status,check\n".to_owned();
+    for r in records {
+        out.push_str(&format!(
+            "{},{},{},{:.3},{},{}\n",
+            r.day,
+            r.part,
+            csv_field(r.answer.as_deref().unwrap_or("")),
+            r.time_ms,
+            csv_field(&r.status),
+            r.check.map(|c| c.to_string()).unwrap_or_default()
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::answers::Verdict;
+    use crate::output::{csv_field, json_string, to_csv, to_json, Record};
+
+    fn records() -> Vec<Record> {
+        vec![
+            Record {
+                day: 1,
+                part: 1,
+                answer: Some("1162".to_owned()),
+                time_ms: 0.5,
+                status: "ok".to_owned(),
+                check: Some(Verdict::Pass),
+            },
+            Record {
+                day: 13,
+                part: 2,
+                answer: Some("\n#.\n.#\n".to_owned()),
+                time_ms: 1.25,
+                status: "ok".to_owned(),
+                check: None,
+            },
+            Record {
+                day: 20,
+                part: 1,
+                answer: None,
+                time_ms: 0.0,
+                status: "failed: day 20 is not implemented".to_owned(),
+                check: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(r#""a\"b\\c\n""#, json_string("a\"b\\c\n"));
+    }
+
+    #[test]
+    fn test_to_json() {
+        assert_eq!(
+            r#"[{"day":1,"part":1,"answer":"1162","time_ms":0.500,"status":"ok","check":"PASS"},{"day":13,"part":2,"answer":"\n#.\n.#\n","time_ms":1.250,"status":"ok","check":null},{"day":20,"part":1,"answer":null,"time_ms":0.000,"status":"failed: day 20 is not implemented","check":null}]"#,
+            to_json(&records())
+        );
+    }
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!("plain", csv_field("plain"));
+        assert_eq!("\"a,b\"", csv_field("a,b"));
+        assert_eq!("\"say \"\"hi\"\"\"", csv_field("say \"hi\""));
+    }
+
+    #[test]
+    fn test_to_csv() {
+        assert_eq!(
+            "day,part,answer,time_ms,status,check
+1,1,1162,0.500,ok,PASS
+13,2,\"
+#.
+.#
+\",1.250,ok,
+20,1,,0.000,failed: day 20 is not implemented,
+",
+            to_csv(&records())
+        );
+    }
+}
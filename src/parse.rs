@@ -0,0 +1,276 @@
+use crate::Error;
+use nom::error::{ErrorKind, FromExternalError};
+use std::fmt;
+
+/// Parser result used by all the day parsers, it keeps track of what was expected at
+/// the position where parsing failed.
+pub type IResult<I, O> = nom::IResult<I, O, ParseError<I>>;
+
+#[derive(PartialEq, Debug)]
+pub struct ParseError<I> {
+    pub input: I,
+    pub expected: String,
+}
+
+fn describe(kind: ErrorKind) -> String {
+    match kind {
+        ErrorKind::Digit => "a digit".to_owned(),
+        ErrorKind::Alpha => "a letter".to_owned(),
+        ErrorKind::AlphaNumeric => "a letter or a digit".to_owned(),
+        ErrorKind::Tag => "a literal".to_owned(),
+        ErrorKind::TakeUntil => "a line ending".to_owned(),
+        ErrorKind::Eof => "more input".to_owned(),
+        ErrorKind::MapRes => "a valid value".to_owned(),
+        kind => format!("{:?}", kind),
+    }
+}
+
+impl<I> nom::error::ParseError<I> for ParseError<I> {
+    fn from_error_kind(input: I, kind: ErrorKind) -> Self {
+        ParseError {
+            input,
+            expected: describe(kind),
+        }
+    }
+
+    fn append(_: I, _: ErrorKind, other: Self) -> Self {
+        other
+    }
+
+    fn from_char(input: I, c: char) -> Self {
+        ParseError {
+            input,
+            expected: format!("{:?}", c),
+        }
+    }
+}
+
+impl<I, E: fmt::Display> FromExternalError<I, E> for ParseError<I> {
+    fn from_external_error(input: I, kind: ErrorKind, e: E) -> Self {
+        ParseError {
+            input,
+            expected: format!("{} ({})", describe(kind), e),
+        }
+    }
+}
+
+/// Like `nom::bytes::complete::tag`, but reports the literal that was expected.
+pub fn tag<'a>(t: &'static str) -> impl Fn(&'a str) -> IResult<&'a str, &'a str> {
+    move |input: &'a str| match input.strip_prefix(t) {
+        Some(rest) => Ok((rest, &input[..t.len()])),
+        None => Err(nom::Err::Error(ParseError {
+            input,
+            expected: format!("{:?}", t),
+        })),
+    }
+}
+
+/// Replaces what `parser` reports as expected on failure with `expected`.
+pub fn expect<'a, O>(
+    mut parser: impl FnMut(&'a str) -> IResult<&'a str, O>,
+    expected: &'static str,
+) -> impl FnMut(&'a str) -> IResult<&'a str, O> {
+    move |input: &'a str| {
+        parser(input).map_err(|e| {
+            e.map(|e| ParseError {
+                input: e.input,
+                expected: expected.to_owned(),
+            })
+        })
+    }
+}
+
+/// Applies `item` until all input is consumed. Unlike `many0` a failing item is an
+/// error instead of the end of the list, so malformed input can't truncate the data.
+pub fn many_to_end<'a, O>(
+    mut item: impl FnMut(&'a str) -> IResult<&'a str, O>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Vec<O>> {
+    move |mut input: &'a str| {
+        let mut items = vec![];
+        while !input.is_empty() {
+            let (rest, o) = item(input)?;
+            if rest.len() == input.len() {
+                return Err(nom::Err::Error(ParseError {
+                    input,
+                    expected: "more input to be consumed".to_owned(),
+                }));
+            }
+            items.push(o);
+            input = rest;
+        }
+
+        Ok((input, items))
+    }
+}
+
+/// Where and why parsing failed.
+#[derive(PartialEq, Debug)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+    pub expected: String,
+    /// The offending line, followed by a line with a caret under the failing column.
+    pub snippet: String,
+}
+
+impl Location {
+    pub fn new(input: &str, rest: &str, expected: String) -> Location {
+        Location::starting_at(1, input, rest, expected)
+    }
+
+    /// Like `new`, for an `input` that starts at line `first_line` of a larger input.
+    pub fn starting_at(first_line: usize, input: &str, rest: &str, expected: String) -> Location {
+        let offset = input.len() - rest.len();
+        let line_start = input[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let line_end = input[offset..]
+            .find('\n')
+            .map(|i| offset + i)
+            .unwrap_or(input.len());
+        let line = input[..offset].matches('\n').count() + first_line;
+        let column = input[line_start..offset].chars().count() + 1;
+
+        let gutter = line.to_string();
+        let snippet = format!(
+            "{} | {}\n{} | {}^",
+            gutter,
+            &input[line_start..line_end],
+            " ".repeat(gutter.len()),
+            " ".repeat(column - 1)
+        );
+
+        Location {
+            line,
+            column,
+            expected,
+            snippet,
+        }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: expected {}\n{}",
+            self.line, self.column, self.expected, self.snippet
+        )
+    }
+}
+
+/// Runs `parser` on the whole input, anything left unparsed is reported as an error.
+pub fn finish<'a, O>(
+    input: &'a str,
+    parser: impl FnMut(&'a str) -> IResult<&'a str, O>,
+) -> Result<O, Error> {
+    finish_at(1, input, parser)
+}
+
+/// Like `finish`, for an `input` that starts at line `first_line` of a streamed input.
+pub fn finish_at<'a, O>(
+    first_line: usize,
+    input: &'a str,
+    mut parser: impl FnMut(&'a str) -> IResult<&'a str, O>,
+) -> Result<O, Error> {
+    let location =
+        |rest, expected| Error::Parse(Location::starting_at(first_line, input, rest, expected));
+
+    match parser(input) {
+        Ok(("", o)) => Ok(o),
+        Ok((rest, _)) => Err(location(rest, "end of input".to_owned())),
+        Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => Err(location(e.input, e.expected)),
+        Err(nom::Err::Incomplete(_)) => Err(location("", "more input".to_owned())),
+    }
+}
+
+/// Checks that a parsed grid has at least one row and that all rows are equally long,
+/// so the solvers can index it freely.
+pub fn rectangular<T>(grid: Vec<Vec<T>>) -> Result<Vec<Vec<T>>, Error> {
+    let width = match grid.first() {
+        Some(first) => first.len(),
+        None => return Err(Error::InvalidState("the grid has no rows".to_owned())),
+    };
+    if let Some(n) = grid.iter().position(|row| row.len() != width) {
+        return Err(Error::InvalidState(format!(
+            "row {} has {} cells, expected {}",
+            n + 1,
+            grid[n].len(),
+            width
+        )));
+    }
+
+    Ok(grid)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse::{finish, finish_at, many_to_end, rectangular, tag, IResult, Location};
+    use crate::Error;
+    use nom::character::complete::digit1;
+
+    fn number_line(input: &str) -> IResult<&str, &str> {
+        let (rest, n) = digit1(input)?;
+        let (rest, _) = tag("\n")(rest)?;
+
+        Ok((rest, n))
+    }
+
+    #[test]
+    fn test_location() {
+        let location = Location::new("12\n345\n", "5\n", "x".to_owned());
+
+        assert_eq!(2, location.line);
+        assert_eq!(3, location.column);
+        assert_eq!("2 | 345\n  |   ^", location.snippet);
+    }
+
+    #[test]
+    fn test_rectangular() {
+        assert!(rectangular(vec![vec![1, 2], vec![3, 4]]).is_ok());
+        assert!(rectangular(vec![vec![1, 2], vec![3]]).is_err());
+        assert!(rectangular::<u8>(vec![]).is_err());
+    }
+
+    #[test]
+    fn test_finish_ok() {
+        assert_eq!(
+            vec!["1", "22"],
+            finish("1\n22\n", many_to_end(number_line)).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_finish_reports_expected_tag() {
+        match finish("1\n22x\n3\n", many_to_end(number_line)) {
+            Err(Error::Parse(location)) => {
+                assert_eq!(2, location.line);
+                assert_eq!(3, location.column);
+                assert_eq!("\"\\n\"", location.expected);
+            }
+            _ => panic!("expected a parse error"),
+        }
+    }
+
+    #[test]
+    fn test_finish_reports_trailing_input() {
+        match finish("1\n2\nx", number_line) {
+            Err(Error::Parse(location)) => {
+                assert_eq!(2, location.line);
+                assert_eq!(1, location.column);
+                assert_eq!("end of input", location.expected);
+            }
+            _ => panic!("expected a parse error"),
+        }
+    }
+
+    #[test]
+    fn test_finish_at_counts_from_first_line() {
+        match finish_at(41, "2x\n", number_line) {
+            Err(Error::Parse(location)) => {
+                assert_eq!(41, location.line);
+                assert_eq!(2, location.column);
+                assert_eq!("41 | 2x\n   |  ^", location.snippet);
+            }
+            _ => panic!("expected a parse error"),
+        }
+    }
+}
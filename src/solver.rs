@@ -0,0 +1,692 @@
+use crate::input::InputSource;
+use crate::output::{Answer, Format, Outcome};
+use crate::Error;
+use crate::{
+    day1, day10, day11, day12, day13, day14, day15, day16, day2, day3, day4, day5, day6, day7,
+    day8, day9,
+};
+use std::any::Any;
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::io::BufRead;
+use std::ops::RangeInclusive;
+use std::panic::{self, AssertUnwindSafe};
+use std::str::FromStr;
+use std::sync::{Condvar, Mutex, MutexGuard, OnceLock, PoisonError};
+use std::thread;
+use std::time::Instant;
+
+/// A solution for one day of the calendar.
+///
+/// The input is parsed once into `Input` and then shared by both parts, which may run
+/// on different threads.
+pub trait Solver: Send + Sync {
+    type Input: Send + Sync + 'static;
+
+    fn day(&self) -> u8;
+    fn name(&self) -> &'static str;
+    fn parse(&self, input: &str) -> Result<Self::Input, Error>;
+    fn part1(&self, input: &Self::Input) -> Result<String, Error>;
+    fn part2(&self, input: &Self::Input) -> Result<String, Error>;
+
+    /// The keys `set_param` accepts.
+    fn params(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Changes one of the constants of the puzzle, e.g. the number of steps to simulate.
+    fn set_param(&mut self, key: &str, _value: &str) -> Result<(), Error> {
+        Err(unknown_param(self.day(), key, self.params()))
+    }
+
+    /// Solves both parts in one pass over `reader`, without holding the whole input in
+    /// memory. `None` for days that need all of the input at once.
+    fn stream(&self, _reader: &mut dyn BufRead) -> Option<Result<(String, String), Error>> {
+        None
+    }
+
+    /// A free form analysis of the input, printed with `--report`. `None` for days that
+    /// have nothing to add to their answers.
+    fn report(&self, _input: &Self::Input) -> Option<Result<String, Error>> {
+        None
+    }
+
+    /// A chart of the input at most `width` characters wide, printed with `--plot`.
+    fn plot(&self, _input: &Self::Input, _width: usize) -> Option<Result<String, Error>> {
+        None
+    }
+
+    /// The intermediate states behind the answers of `parts`, printed with `--export`.
+    fn export(
+        &self,
+        _input: &Self::Input,
+        _parts: &[u8],
+        _format: &Format,
+    ) -> Option<Result<String, Error>> {
+        None
+    }
+}
+
+/// Error for a parameter that `day` doesn't have, listing the ones it does.
+pub fn unknown_param(day: u8, key: &str, known: &[&str]) -> Error {
+    if known.is_empty() {
+        Error::Config(format!("day {} has no parameters, got '{}'", day, key))
+    } else {
+        Error::Config(format!(
+            "day {} has no parameter '{}', expected one of {}",
+            day,
+            key,
+            known.join(", ")
+        ))
+    }
+}
+
+/// Parses the value of parameter `key` and checks that it is within `range`.
+pub fn parse_param<T>(key: &str, value: &str, range: RangeInclusive<T>) -> Result<T, Error>
+where
+    T: FromStr + PartialOrd + Display,
+    T::Err: Display,
+{
+    let parsed: T = value
+        .parse()
+        .map_err(|e| Error::Config(format!("invalid value '{}' for {}: {}", value, key, e)))?;
+    if !range.contains(&parsed) {
+        return Err(Error::Config(format!(
+            "{} must be between {} and {}, got {}",
+            key,
+            range.start(),
+            range.end(),
+            parsed
+        )));
+    }
+
+    Ok(parsed)
+}
+
+/// A `key=value` override of a puzzle constant, given with `--param`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Param {
+    pub key: String,
+    pub value: String,
+}
+
+impl FromStr for Param {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => Ok(Param {
+                key: key.trim().to_owned(),
+                value: value.trim().to_owned(),
+            }),
+            _ => Err(format!("invalid parameter '{}', expected key=value", s)),
+        }
+    }
+}
+
+/// A parsed input as handed out by [`Runner::parse`].
+pub type Parsed = Box<dyn Any + Send + Sync>;
+
+/// Type erased view of a [`Solver`], so that every day can live in the same registry.
+pub trait Runner: Send + Sync {
+    fn day(&self) -> u8;
+    fn name(&self) -> &'static str;
+    fn parse(&self, input: &str) -> Result<Parsed, Error>;
+    fn part1(&self, input: &dyn Any) -> Result<String, Error>;
+    fn part2(&self, input: &dyn Any) -> Result<String, Error>;
+    fn params(&self) -> &'static [&'static str];
+    fn set_param(&mut self, key: &str, value: &str) -> Result<(), Error>;
+    fn stream(&self, reader: &mut dyn BufRead) -> Option<Result<(String, String), Error>>;
+    fn report(&self, input: &dyn Any) -> Option<Result<String, Error>>;
+    fn plot(&self, input: &dyn Any, width: usize) -> Option<Result<String, Error>>;
+    fn export(
+        &self,
+        input: &dyn Any,
+        parts: &[u8],
+        format: &Format,
+    ) -> Option<Result<String, Error>>;
+}
+
+struct Registered<S>(S);
+
+impl<S: Solver> Registered<S> {
+    fn input<'a>(&self, input: &'a dyn Any) -> Result<&'a S::Input, Error> {
+        input
+            .downcast_ref()
+            .ok_or_else(|| Error::InvalidState("input was not parsed by this solver".to_owned()))
+    }
+}
+
+impl<S: Solver> Runner for Registered<S> {
+    fn day(&self) -> u8 {
+        self.0.day()
+    }
+
+    fn name(&self) -> &'static str {
+        self.0.name()
+    }
+
+    fn parse(&self, input: &str) -> Result<Parsed, Error> {
+        Ok(Box::new(self.0.parse(input)?))
+    }
+
+    fn part1(&self, input: &dyn Any) -> Result<String, Error> {
+        self.0.part1(self.input(input)?)
+    }
+
+    fn part2(&self, input: &dyn Any) -> Result<String, Error> {
+        self.0.part2(self.input(input)?)
+    }
+
+    fn params(&self) -> &'static [&'static str] {
+        self.0.params()
+    }
+
+    fn set_param(&mut self, key: &str, value: &str) -> Result<(), Error> {
+        self.0.set_param(key, value)
+    }
+
+    fn stream(&self, reader: &mut dyn BufRead) -> Option<Result<(String, String), Error>> {
+        self.0.stream(reader)
+    }
+
+    fn report(&self, input: &dyn Any) -> Option<Result<String, Error>> {
+        match self.input(input) {
+            Ok(input) => self.0.report(input),
+            Err(err) => Some(Err(err)),
+        }
+    }
+
+    fn plot(&self, input: &dyn Any, width: usize) -> Option<Result<String, Error>> {
+        match self.input(input) {
+            Ok(input) => self.0.plot(input, width),
+            Err(err) => Some(Err(err)),
+        }
+    }
+
+    fn export(
+        &self,
+        input: &dyn Any,
+        parts: &[u8],
+        format: &Format,
+    ) -> Option<Result<String, Error>> {
+        match self.input(input) {
+            Ok(input) => self.0.export(input, parts, format),
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+/// All implemented days, in calendar order.
+pub fn registry() -> Vec<Box<dyn Runner>> {
+    vec![
+        Box::new(Registered(day1::Day1::default())),
+        Box::new(Registered(day2::Day2::default())),
+        Box::new(Registered(day3::Day3)),
+        Box::new(Registered(day4::Day4::default())),
+        Box::new(Registered(day5::Day5)),
+        Box::new(Registered(day6::Day6::default())),
+        Box::new(Registered(day7::Day7)),
+        Box::new(Registered(day8::Day8)),
+        Box::new(Registered(day9::Day9)),
+        Box::new(Registered(day10::Day10)),
+        Box::new(Registered(day11::Day11::default())),
+        Box::new(Registered(day12::Day12)),
+        Box::new(Registered(day13::Day13)),
+        Box::new(Registered(day14::Day14::default())),
+        Box::new(Registered(day15::Day15::default())),
+        Box::new(Registered(day16::Day16)),
+    ]
+}
+
+pub fn find(day: u8) -> Option<Box<dyn Runner>> {
+    registry().into_iter().find(|r| r.day() == day)
+}
+
+/// The `params` for `day` when it runs together with `days`. A key only goes to the days
+/// that have it, unless none of them does, then every day gets it to report the error.
+pub fn params_for(day: u8, days: &[u8], params: &[Param]) -> Vec<Param> {
+    let has = |day: u8, key: &str| find(day).is_some_and(|r| r.params().contains(&key));
+
+    params
+        .iter()
+        .filter(|p| has(day, &p.key) || !days.iter().any(|d| has(*d, &p.key)))
+        .cloned()
+        .collect()
+}
+
+fn configured(day: u8, params: &[Param]) -> Result<Box<dyn Runner>, Error> {
+    let mut runner = find(day).ok_or(Error::UnsupportedDay(day))?;
+    for param in params {
+        runner.set_param(&param.key, &param.value)?;
+    }
+
+    Ok(runner)
+}
+
+fn calculate(
+    day: u8,
+    input: &InputSource,
+    parts: &[u8],
+    params: &[Param],
+) -> Result<Vec<Answer>, Error> {
+    let runner = configured(day, params)?;
+    let parsed = runner.parse(&input.read(day)?)?;
+
+    Ok(parts
+        .iter()
+        .map(|part| {
+            let start = Instant::now();
+            let result = match part {
+                1 => runner.part1(parsed.as_ref()),
+                _ => runner.part2(parsed.as_ref()),
+            };
+
+            Answer {
+                part: *part,
+                result,
+                elapsed: start.elapsed(),
+                verdict: None,
+            }
+        })
+        .collect())
+}
+
+/// Parses the input of `day` once and runs the selected parts, timing each of them.
+/// `params` override the puzzle constants of the day.
+pub fn run(day: u8, input: &InputSource, parts: &[u8], params: &[Param]) -> Outcome {
+    let start = Instant::now();
+    let result = calculate(day, input, parts, params);
+
+    Outcome {
+        day,
+        result,
+        elapsed: start.elapsed(),
+    }
+}
+
+fn calculate_streaming(
+    day: u8,
+    input: &InputSource,
+    parts: &[u8],
+    params: &[Param],
+) -> Result<Vec<Answer>, Error> {
+    let runner = configured(day, params)?;
+    let mut reader = input.open(day)?;
+
+    let start = Instant::now();
+    let (part1, part2) = runner
+        .stream(reader.as_mut())
+        .ok_or_else(|| Error::Config(format!("day {} can't be streamed", day)))??;
+    let elapsed = start.elapsed();
+
+    Ok(parts
+        .iter()
+        .map(|part| Answer {
+            part: *part,
+            result: Ok(if *part == 1 {
+                part1.clone()
+            } else {
+                part2.clone()
+            }),
+            elapsed,
+            verdict: None,
+        })
+        .collect())
+}
+
+/// Like `run`, but reads the input line by line in bounded memory. Both parts are
+/// solved in the same pass, so each answer reports the time of the whole pass.
+pub fn stream(day: u8, input: &InputSource, parts: &[u8], params: &[Param]) -> Outcome {
+    let start = Instant::now();
+    let result = calculate_streaming(day, input, parts, params);
+
+    Outcome {
+        day,
+        result,
+        elapsed: start.elapsed(),
+    }
+}
+
+/// Parses the input of `day` and returns its report.
+pub fn report(day: u8, input: &InputSource, params: &[Param]) -> Result<String, Error> {
+    let runner = configured(day, params)?;
+    let parsed = runner.parse(&input.read(day)?)?;
+
+    runner
+        .report(parsed.as_ref())
+        .unwrap_or_else(|| Err(Error::Config(format!("day {} has no report", day))))
+}
+
+/// Parses the input of `day` and draws it `width` characters wide.
+pub fn plot(day: u8, input: &InputSource, params: &[Param], width: usize) -> Result<String, Error> {
+    let runner = configured(day, params)?;
+    let parsed = runner.parse(&input.read(day)?)?;
+
+    runner
+        .plot(parsed.as_ref(), width)
+        .unwrap_or_else(|| Err(Error::Config(format!("day {} has no plot", day))))
+}
+
+/// Parses the input of `day` and exports what led to the answers of `parts`.
+pub fn export(
+    day: u8,
+    input: &InputSource,
+    parts: &[u8],
+    params: &[Param],
+    format: &Format,
+) -> Result<String, Error> {
+    let runner = configured(day, params)?;
+    let parsed = runner.parse(&input.read(day)?)?;
+
+    runner
+        .export(parsed.as_ref(), parts, format)
+        .unwrap_or_else(|| Err(Error::Config(format!("day {} has nothing to export", day))))
+}
+
+/// One unit of work for the pool in [`run_all`].
+enum Task {
+    Parse(usize),
+    Part(usize, u8),
+}
+
+struct Queue {
+    tasks: VecDeque<Task>,
+    busy: usize,
+}
+
+/// The state of one day while its tasks are being worked on.
+struct Slot {
+    day: u8,
+    parsed: OnceLock<(Box<dyn Runner>, Parsed)>,
+    progress: Mutex<Progress>,
+}
+
+struct Progress {
+    start: Option<Instant>,
+    end: Option<Instant>,
+    result: Result<Vec<Answer>, Error>,
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl Slot {
+    /// Parses the input and returns a task for every part, or none when parsing failed.
+    fn start(&self, index: usize, parts: &[u8], parse: &ParseFn<'_>) -> Vec<Task> {
+        lock(&self.progress).start = Some(Instant::now());
+        let result = parse(self.day, index);
+
+        let mut progress = lock(&self.progress);
+        progress.end = Some(Instant::now());
+        match result {
+            Ok(parsed) => {
+                let _ = self.parsed.set(parsed);
+                parts.iter().map(|part| Task::Part(index, *part)).collect()
+            }
+            Err(err) => {
+                progress.result = Err(err);
+                vec![]
+            }
+        }
+    }
+
+    fn solve(&self, part: u8) {
+        let start = Instant::now();
+        let result = match self.parsed.get() {
+            Some((runner, parsed)) if part == 1 => runner.part1(parsed.as_ref()),
+            Some((runner, parsed)) => runner.part2(parsed.as_ref()),
+            None => Err(Error::InvalidState("part run before parsing".to_owned())),
+        };
+        let answer = Answer {
+            part,
+            result,
+            elapsed: start.elapsed(),
+            verdict: None,
+        };
+
+        let mut progress = lock(&self.progress);
+        progress.end = Some(Instant::now());
+        if let Ok(answers) = &mut progress.result {
+            answers.push(answer);
+            answers.sort_by_key(|a| a.part);
+        }
+    }
+
+    /// Turns the day into an error row after one of its tasks panicked.
+    fn fail(&self, panic: Box<dyn Any + Send>) {
+        let message = match panic.downcast::<String>() {
+            Ok(message) => *message,
+            Err(panic) => match panic.downcast::<&str>() {
+                Ok(message) => (*message).to_owned(),
+                Err(_) => "no message".to_owned(),
+            },
+        };
+
+        let mut progress = lock(&self.progress);
+        progress.end = Some(Instant::now());
+        progress.result = Err(Error::InvalidState(format!(
+            "the solver panicked: {}",
+            message
+        )));
+    }
+
+    fn into_outcome(self) -> Outcome {
+        let progress = self
+            .progress
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        Outcome {
+            day: self.day,
+            result: progress.result,
+            elapsed: match (progress.start, progress.end) {
+                (Some(start), Some(end)) => end - start,
+                _ => Default::default(),
+            },
+        }
+    }
+}
+
+/// Runs every day in `days` like [`run`], but on `jobs` threads. Once a day is parsed
+/// its parts are queued separately, so they can run at the same time as well. The
+/// outcomes are returned in the order of `days`.
+pub fn run_all(
+    days: &[u8],
+    input: &InputSource,
+    parts: &[u8],
+    params: &[Param],
+    jobs: usize,
+) -> Vec<Outcome> {
+    let params: Vec<Vec<Param>> = days.iter().map(|d| params_for(*d, days, params)).collect();
+    if jobs <= 1 {
+        return days
+            .iter()
+            .zip(&params)
+            .map(|(d, params)| run(*d, input, parts, params))
+            .collect();
+    }
+
+    let parse = |day: u8, index: usize| {
+        let runner = configured(day, &params[index])?;
+        let parsed = runner.parse(&input.read(day)?)?;
+
+        Ok((runner, parsed))
+    };
+
+    run_pool(days, parts, jobs, &parse)
+}
+
+/// Parses the input of a day, given the day and its index in the pool.
+type ParseFn<'a> = dyn Fn(u8, usize) -> Result<(Box<dyn Runner>, Parsed), Error> + Sync + 'a;
+
+/// The thread pool behind [`run_all`]. A task that panics turns its day into an error
+/// row instead of taking its worker down, so the other workers still see the queue drain.
+fn run_pool(days: &[u8], parts: &[u8], jobs: usize, parse: &ParseFn<'_>) -> Vec<Outcome> {
+    let slots: Vec<Slot> = days
+        .iter()
+        .map(|day| Slot {
+            day: *day,
+            parsed: OnceLock::new(),
+            progress: Mutex::new(Progress {
+                start: None,
+                end: None,
+                result: Ok(vec![]),
+            }),
+        })
+        .collect();
+    let queue = Mutex::new(Queue {
+        tasks: (0..days.len()).map(Task::Parse).collect(),
+        busy: 0,
+    });
+    let ready = Condvar::new();
+
+    let worker = || loop {
+        let task = {
+            let mut queue = lock(&queue);
+            loop {
+                if let Some(task) = queue.tasks.pop_front() {
+                    queue.busy += 1;
+                    break task;
+                }
+                if queue.busy == 0 {
+                    return;
+                }
+                queue = ready.wait(queue).unwrap_or_else(PoisonError::into_inner);
+            }
+        };
+
+        let index = match task {
+            Task::Parse(index) | Task::Part(index, _) => index,
+        };
+        let follow_up = panic::catch_unwind(AssertUnwindSafe(|| match task {
+            Task::Parse(index) => slots[index].start(index, parts, parse),
+            Task::Part(index, part) => {
+                slots[index].solve(part);
+                vec![]
+            }
+        }))
+        .unwrap_or_else(|panic| {
+            slots[index].fail(panic);
+            vec![]
+        });
+
+        let mut queue = lock(&queue);
+        queue.tasks.extend(follow_up);
+        queue.busy -= 1;
+        ready.notify_all();
+    };
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.min(days.len() * parts.len().max(1)) {
+            scope.spawn(worker);
+        }
+    });
+
+    slots.into_iter().map(Slot::into_outcome).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solver::{
+        find, parse_param, registry, run_pool, Param, Parsed, Registered, Runner, Solver,
+    };
+    use crate::Error;
+    use std::str::FromStr;
+
+    struct Panicking;
+
+    impl Solver for Panicking {
+        type Input = ();
+
+        fn day(&self) -> u8 {
+            1
+        }
+
+        fn name(&self) -> &'static str {
+            "Panicking"
+        }
+
+        fn parse(&self, _input: &str) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn part1(&self, _input: &()) -> Result<String, Error> {
+            panic!("part 1 is broken")
+        }
+
+        fn part2(&self, _input: &()) -> Result<String, Error> {
+            Ok("2".to_owned())
+        }
+    }
+
+    #[test]
+    fn test_registry_in_calendar_order() {
+        let days: Vec<u8> = registry().iter().map(|r| r.day()).collect();
+
+        assert_eq!((1..=16).collect::<Vec<u8>>(), days);
+    }
+
+    #[test]
+    fn test_solve_through_registry() {
+        let day7 = &registry()[6];
+        let parsed = day7.parse("16,1,2,0,4,2,7,1,2,14\n").unwrap();
+
+        assert_eq!("37", day7.part1(parsed.as_ref()).unwrap());
+        assert_eq!("168", day7.part2(parsed.as_ref()).unwrap());
+    }
+
+    #[test]
+    fn test_param_from_str() {
+        assert_eq!(
+            Param {
+                key: "steps".to_owned(),
+                value: "500".to_owned()
+            },
+            Param::from_str("steps=500").unwrap()
+        );
+        assert!(Param::from_str("steps").is_err());
+        assert!(Param::from_str("=500").is_err());
+    }
+
+    #[test]
+    fn test_parse_param() {
+        assert_eq!(3, parse_param("window", "3", 1..=10).unwrap());
+        assert!(parse_param("window", "0", 1..=10).is_err());
+        assert!(parse_param("window", "x", 1..=10).is_err());
+    }
+
+    #[test]
+    fn test_set_param() {
+        let mut day6 = find(6).unwrap();
+        let parsed = day6.parse("3,4,3,1,2\n").unwrap();
+        day6.set_param("days", "18").unwrap();
+
+        assert_eq!("26", day6.part1(parsed.as_ref()).unwrap());
+        assert!(day6.set_param("steps", "18").is_err());
+        assert!(find(7).unwrap().set_param("days", "18").is_err());
+    }
+
+    #[test]
+    fn test_pool_survives_a_panicking_solver() {
+        let parse = |day: u8, _index: usize| {
+            let runner: Box<dyn Runner> = match day {
+                1 => Box::new(Registered(Panicking)),
+                _ => find(day).ok_or(Error::UnsupportedDay(day))?,
+            };
+            let parsed: Parsed = runner.parse("16,1,2,0,4,2,7,1,2,14\n")?;
+
+            Ok((runner, parsed))
+        };
+        let outcomes = run_pool(&[1, 7], &[1, 2], 2, &parse);
+
+        assert!(matches!(
+            &outcomes[0].result,
+            Err(Error::InvalidState(message)) if message.contains("part 1 is broken")
+        ));
+        let answers = outcomes[1].result.as_ref().unwrap();
+        assert_eq!("37", answers[0].result.as_ref().unwrap());
+        assert_eq!("168", answers[1].result.as_ref().unwrap());
+    }
+}
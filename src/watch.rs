@@ -0,0 +1,157 @@
+use crate::input::InputSource;
+use crate::output::Outcome;
+use crate::solver::{self, Param};
+use crate::Error;
+use inotify::{Inotify, WatchMask};
+use std::path::{Path, PathBuf};
+
+/// The answer, or the error, of every part in one run.
+type Results = Vec<(u8, String)>;
+
+fn results(outcome: &Outcome, parts: &[u8]) -> Results {
+    match &outcome.result {
+        Ok(answers) => answers
+            .iter()
+            .map(|a| match &a.result {
+                Ok(answer) => (a.part, answer.clone()),
+                Err(err) => (a.part, format!("failed: {}", err)),
+            })
+            .collect(),
+        Err(err) => parts
+            .iter()
+            .map(|part| (*part, format!("failed: {}", err)))
+            .collect(),
+    }
+}
+
+/// Describes every result of `current` compared to the same part in `previous`.
+fn diff(previous: Option<&Results>, current: &Results) -> Vec<String> {
+    current
+        .iter()
+        .map(|(part, result)| {
+            let before = previous
+                .and_then(|p| p.iter().find(|(p, _)| p == part))
+                .map(|(_, r)| r);
+            match before {
+                None => format!("part {}: {}", part, result),
+                Some(b) if b == result => format!("part {}: {} (unchanged)", part, result),
+                Some(b) if b.contains('\n') || result.contains('\n') => {
+                    format!("part {} (changed): {}", part, result)
+                }
+                Some(b) => format!("part {}: {} (was {})", part, result, b),
+            }
+        })
+        .collect()
+}
+
+struct Watched {
+    path: PathBuf,
+    previous: Option<Results>,
+}
+
+impl Watched {
+    fn run(&mut self, day: u8, parts: &[u8], params: &[Param]) {
+        let outcome = solver::run(day, &InputSource::Path(self.path.clone()), parts, params);
+        let current = results(&outcome, parts);
+
+        println!(
+            "day {} on {} ({:.3} ms)",
+            day,
+            self.path.display(),
+            outcome.elapsed.as_secs_f64() * 1000.0
+        );
+        for line in diff(self.previous.as_ref(), &current) {
+            println!("{}", line);
+        }
+        self.previous = Some(current);
+    }
+}
+
+/// Runs `day` on its input, and on `example` if given, then again on every file that
+/// changes until the process is stopped.
+pub fn watch(
+    day: u8,
+    input: &InputSource,
+    example: Option<&Path>,
+    parts: &[u8],
+    params: &[Param],
+) -> Result<(), Error> {
+    let input = input
+        .file(day)
+        .ok_or_else(|| Error::Config("--watch needs the input in a file".to_owned()))?;
+
+    let mut inotify = Inotify::init()?;
+    let mut watched = vec![];
+    for path in std::iter::once(input).chain(example.map(Path::to_path_buf)) {
+        // Editors often save by replacing the file, which only shows up on the directory.
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let wd = inotify.add_watch(&dir, WatchMask::CLOSE_WRITE | WatchMask::MOVED_TO)?;
+        watched.push((
+            wd,
+            Watched {
+                path,
+                previous: None,
+            },
+        ));
+    }
+
+    for (_, w) in &mut watched {
+        w.run(day, parts, params);
+    }
+
+    let mut buffer = [0; 4096];
+    loop {
+        let mut changed = vec![false; watched.len()];
+        for event in inotify.read_events_blocking(&mut buffer)? {
+            for (i, (wd, w)) in watched.iter().enumerate() {
+                if event.wd == *wd && event.name == w.path.file_name() {
+                    changed[i] = true;
+                }
+            }
+        }
+
+        for ((_, w), changed) in watched.iter_mut().zip(changed) {
+            if changed {
+                println!();
+                w.run(day, parts, params);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::watch::diff;
+
+    #[test]
+    fn test_diff_first_run() {
+        let current = vec![(1, "7".to_owned()), (2, "5".to_owned())];
+
+        assert_eq!(vec!["part 1: 7", "part 2: 5"], diff(None, &current));
+    }
+
+    #[test]
+    fn test_diff_against_previous_run() {
+        let previous = vec![(1, "7".to_owned()), (2, "5".to_owned())];
+        let current = vec![(1, "7".to_owned()), (2, "6".to_owned())];
+
+        assert_eq!(
+            vec!["part 1: 7 (unchanged)", "part 2: 6 (was 5)"],
+            diff(Some(&previous), &current)
+        );
+    }
+
+    #[test]
+    fn test_diff_multiline_answer() {
+        let previous = vec![(2, "\n#.\n".to_owned())];
+        let current = vec![(2, "\n.#\n".to_owned())];
+
+        assert_eq!(
+            vec!["part 2 (changed): \n.#\n"],
+            diff(Some(&previous), &current)
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use adventofcode2021::input::InputSource;
+use adventofcode2021::output::{Answer, Outcome};
+use adventofcode2021::solver::Param;
+use adventofcode2021::{day16, day7, solver};
+use std::path::PathBuf;
+
+#[test]
+fn test_day_functions_are_public() {
+    let crabs = day7::parse_input("16,1,2,0,4,2,7,1,2,14\n").unwrap();
+
+    assert_eq!("37", day7::part1(&crabs).unwrap());
+    assert_eq!("168", day7::part2(&crabs).unwrap());
+}
+
+#[test]
+fn test_bits_decoder() {
+    let bits = day16::parse_input("C200B40A82\n").unwrap();
+    let packet = day16::decode(&bits).unwrap();
+
+    assert_eq!(3, day16::eval(&packet).unwrap());
+}
+
+#[test]
+fn test_registry() {
+    let runner = solver::find(7).unwrap();
+
+    assert_eq!("The Treachery of Whales", runner.name());
+}
+
+#[test]
+fn test_streaming_matches_batch() {
+    let input = InputSource::Path(PathBuf::from("input"));
+
+    for day in [1, 2, 5, 10] {
+        let batch = solver::run(day, &input, &[1, 2], &[]).result.unwrap();
+        let streamed = solver::stream(day, &input, &[1, 2], &[]).result.unwrap();
+
+        let answers = |answers: Vec<Answer>| -> Vec<String> {
+            answers.into_iter().map(|a| a.result.unwrap()).collect()
+        };
+        assert_eq!(answers(batch), answers(streamed), "day {}", day);
+    }
+}
+
+#[test]
+fn test_parallel_run_matches_sequential() {
+    let input = InputSource::Path(PathBuf::from("input"));
+    let days: Vec<u8> = (1..=16).collect();
+
+    let answers = |outcomes: Vec<Outcome>| -> Vec<(u8, Vec<String>)> {
+        outcomes
+            .into_iter()
+            .map(|o| {
+                let answers = o.result.unwrap();
+                (
+                    o.day,
+                    answers.into_iter().map(|a| a.result.unwrap()).collect(),
+                )
+            })
+            .collect()
+    };
+
+    assert_eq!(
+        answers(solver::run_all(&days, &input, &[1, 2], &[], 1)),
+        answers(solver::run_all(&days, &input, &[1, 2], &[], 4))
+    );
+}
+
+#[test]
+fn test_report() {
+    let input = InputSource::Path(PathBuf::from("input"));
+
+    let report = solver::report(1, &input, &[]).unwrap();
+    assert!(report.contains("increases with window 3: 1190"));
+
+    assert!(matches!(
+        solver::report(4, &input, &[]),
+        Err(adventofcode2021::Error::Config(_))
+    ));
+}
+
+#[test]
+fn test_params_across_days() {
+    let input = InputSource::Path(PathBuf::from("input"));
+    let steps: Vec<Param> = vec!["steps=5".parse().unwrap()];
+
+    let outcomes = solver::run_all(&[11, 14], &input, &[1], &steps, 1);
+    for (outcome, day) in outcomes.into_iter().zip([11, 14]) {
+        let alone = solver::run(day, &input, &[1], &steps);
+        assert_eq!(
+            alone.result.unwrap()[0].result.as_ref().unwrap(),
+            outcome.result.unwrap()[0].result.as_ref().unwrap(),
+            "day {}",
+            day
+        );
+    }
+
+    // Day 7 has no steps, but it's skipped as long as another selected day has them.
+    let outcomes = solver::run_all(&[7, 14], &input, &[1], &steps, 2);
+    assert!(outcomes.iter().all(|o| o.result.is_ok()));
+    assert!(solver::run(7, &input, &[1], &steps).result.is_err());
+
+    let unknown: Param = "nope=1".parse().unwrap();
+    for outcome in solver::run_all(&[11, 14], &input, &[1], &[unknown], 1) {
+        assert!(matches!(
+            outcome.result,
+            Err(adventofcode2021::Error::Config(_))
+        ));
+    }
+}
@@ -0,0 +1,180 @@
+//! Fuzzes every day's parser and the BITS packet decoder with arbitrary input. Each
+//! case has to return instead of panicking, finish within `TIMEOUT` and allocate no
+//! more than `allocation_limit` bytes. Run longer with e.g.
+//! `PROPTEST_CASES=100000 cargo test --release --test fuzz`.
+
+use adventofcode2021::{day16, solver};
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::test_runner::TestCaseError;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Counts the bytes allocated by the current thread, so a case can be checked for
+/// runaway memory use without being disturbed by tests running in parallel.
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATED: Cell<usize> = const { Cell::new(0) };
+}
+
+fn count(bytes: usize) {
+    let _ = ALLOCATED.try_with(|a| a.set(a.get().saturating_add(bytes)));
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count(layout.size());
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count(new_size.saturating_sub(layout.size()));
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn allocation_limit(input_len: usize) -> usize {
+    64 * 1024 + 256 * input_len
+}
+
+/// Runs `f` on its own thread and fails the case if it panics, doesn't finish in time
+/// or allocates more than the limit for an input of `input_len` bytes.
+fn check(input_len: usize, f: impl FnOnce() + Send + 'static) -> Result<(), TestCaseError> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        ALLOCATED.with(|a| a.set(0));
+        let finished = panic::catch_unwind(AssertUnwindSafe(f)).is_ok();
+        let allocated = ALLOCATED.with(|a| a.get());
+        let _ = tx.send((finished, allocated));
+    });
+
+    match rx.recv_timeout(TIMEOUT) {
+        Ok((finished, allocated)) => {
+            prop_assert!(finished, "panicked");
+            prop_assert!(
+                allocated <= allocation_limit(input_len),
+                "allocated {} bytes for {} bytes of input",
+                allocated,
+                input_len
+            );
+            Ok(())
+        }
+        Err(_) => Err(TestCaseError::fail(format!(
+            "didn't finish within {:?}",
+            TIMEOUT
+        ))),
+    }
+}
+
+fn check_parse(day: u8, input: String) -> Result<(), TestCaseError> {
+    check(input.len(), move || {
+        let runner = solver::find(day).unwrap();
+        let _ = runner.parse(&input);
+    })
+}
+
+/// Defines a test feeding day `$day`'s parser both arbitrary bytes and text made from
+/// `$alphabet`, which gets much further into the parser than random bytes do.
+macro_rules! fuzz_parser {
+    ($name:ident, $day:expr, $alphabet:expr) => {
+        proptest! {
+            #[test]
+            fn $name(bytes in vec(any::<u8>(), 0..512), text in $alphabet) {
+                check_parse($day, String::from_utf8_lossy(&bytes).into_owned())?;
+                check_parse($day, text)?;
+            }
+        }
+    };
+}
+
+fuzz_parser!(fuzz_day1, 1, "[0-9\n]{0,300}");
+fuzz_parser!(
+    fuzz_day2,
+    2,
+    "( *(forward|back|down|up|turn|set-aim|repeat|[a-z]{1,3}) ?-?[0-9]{0,12}( \\{)?\n?| *\\}\n){0,30}"
+);
+fuzz_parser!(fuzz_day3, 3, "[0-2\n]{0,300}");
+fuzz_parser!(fuzz_day4, 4, "[0-9, \n]{0,400}");
+fuzz_parser!(fuzz_day5, 5, "([0-9,]{0,8}( -> )?[0-9,]{0,8}\n?){0,30}");
+fuzz_parser!(fuzz_day6, 6, "[0-9,\n]{0,300}");
+fuzz_parser!(fuzz_day7, 7, "[0-9,\n]{0,300}");
+fuzz_parser!(fuzz_day8, 8, "[a-h |\n]{0,300}");
+fuzz_parser!(fuzz_day9, 9, "[0-9\n]{0,300}");
+fuzz_parser!(fuzz_day10, 10, "[\\[\\](){}<>x\n]{0,300}");
+fuzz_parser!(fuzz_day11, 11, "[0-9\n]{0,300}");
+fuzz_parser!(
+    fuzz_day12,
+    12,
+    "((start|end|[a-cA-C]{1,2})-?(start|end|[a-cA-C]{1,2})\n?){0,30}"
+);
+fuzz_parser!(
+    fuzz_day13,
+    13,
+    "([0-9,]{0,8}\n|fold along [xyz]=[0-9]{0,4}\n|\n){0,30}"
+);
+fuzz_parser!(fuzz_day14, 14, "([A-D]{0,6}( -> )?[A-D]{0,2}\n?){0,30}");
+fuzz_parser!(fuzz_day15, 15, "[0-9\n]{0,300}");
+fuzz_parser!(fuzz_day16, 16, "[0-9A-Fa-g\n]{0,300}");
+
+/// A transmission of `depth` operators, each holding the next one, around a literal.
+/// Random bytes hardly ever nest this deep or stop in the middle of a sub-packet.
+fn nested_transmission(depth: usize) -> Vec<u8> {
+    let mut bits = "000000100000000001".repeat(depth) + "00010000001";
+    while !bits.len().is_multiple_of(8) {
+        bits.push('0');
+    }
+
+    bits.as_bytes()
+        .chunks(8)
+        .map(|byte| u8::from_str_radix(std::str::from_utf8(byte).unwrap(), 2).unwrap())
+        .collect()
+}
+
+proptest! {
+    #[test]
+    fn fuzz_nested_packets(depth in 0..2 * day16::MAX_DEPTH) {
+        let bytes = nested_transmission(depth);
+        check(bytes.len(), move || {
+            assert_eq!(depth < day16::MAX_DEPTH, day16::decode(&bytes).is_ok());
+        })?;
+    }
+
+    #[test]
+    fn fuzz_truncated_packets(depth in 0..2 * day16::MAX_DEPTH, cut in any::<prop::sample::Index>()) {
+        let mut bytes = nested_transmission(depth);
+        bytes.truncate(cut.index(bytes.len()));
+        check(bytes.len(), move || {
+            assert!(day16::decode(&bytes).is_err());
+        })?;
+    }
+
+    #[test]
+    fn fuzz_to_packets(bytes in vec(any::<u8>(), 0..512)) {
+        check(bytes.len(), move || {
+            let _ = day16::to_packets((&bytes, 0));
+        })?;
+    }
+
+    #[test]
+    fn fuzz_decode_and_eval(bytes in vec(any::<u8>(), 0..512)) {
+        check(bytes.len(), move || {
+            if let Ok(packet) = day16::decode(&bytes) {
+                let _ = day16::eval(&packet);
+            }
+        })?;
+    }
+}
@@ -0,0 +1,140 @@
+use adventofcode2021::solver::{registry, Runner};
+use adventofcode2021::Error;
+use std::fs::read_to_string;
+
+const GARBAGE: &[&str] = &[
+    "garbage\n",
+    "\u{1F980}\n",
+    "\n\n\n",
+    " 1 2\n",
+    "-1\n",
+    "1,2,3\n",
+    "a-b\nb-c\n",
+    "99999999999999999999\n",
+    "forward x\n",
+    "0,0 -> 1\n",
+];
+
+fn run(runner: &dyn Runner, input: &str) -> Result<(String, String), Error> {
+    let parsed = runner.parse(input)?;
+
+    Ok((
+        runner.part1(parsed.as_ref())?,
+        runner.part2(parsed.as_ref())?,
+    ))
+}
+
+fn assert_fails(day: u8, input: &str) {
+    let runner = adventofcode2021::solver::find(day).unwrap();
+
+    assert!(
+        run(runner.as_ref(), input).is_err(),
+        "day {} accepted {:?}",
+        day,
+        input
+    );
+}
+
+#[test]
+fn test_empty_input() {
+    for runner in registry() {
+        // Only checks that nothing panics, an empty list is a valid input for some days.
+        let _ = run(runner.as_ref(), "");
+        let _ = run(runner.as_ref(), "\n");
+    }
+}
+
+#[test]
+fn test_garbage_input() {
+    for runner in registry() {
+        for garbage in GARBAGE {
+            let _ = run(runner.as_ref(), garbage);
+        }
+        assert!(run(runner.as_ref(), "garbage\n").is_err());
+    }
+}
+
+#[test]
+fn test_truncated_input() {
+    for runner in registry() {
+        let input = read_to_string(format!("input/day{}", runner.day())).unwrap();
+        let lines: Vec<&str> = input.split_inclusive('\n').collect();
+
+        for n in 1..=lines.len().min(4) {
+            let prefix: String = lines[..n].concat();
+            let _ = run(runner.as_ref(), &prefix);
+
+            let cut = prefix.len() - lines[n - 1].len() / 2 - 1;
+            if prefix.is_char_boundary(cut) {
+                let _ = run(runner.as_ref(), &prefix[..cut]);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_known_bad_inputs() {
+    assert_fails(
+        4,
+        "1,2\n\n 1  2  3  4  5\n 6  7  8  9 10\n11 12 13 14 15\n16 17 18 19 20\n21 22 23 24\n",
+    );
+    assert_fails(6, "3,4,9\n");
+    assert_fails(7, "");
+    assert_fails(
+        8,
+        "be cfbegad cbdgef fgaecd cgeb fdcge agebfd fecdb fabcd edb | fdgacbe cefdb cefbgd gch\n",
+    );
+    assert_fails(10, "[({(<(())[]>[[{[]{<()<>>\n[(()[<>])]({[<{<<[]>>x\n");
+    assert_fails(10, "[]\n");
+    assert_fails(11, "123\n456\n");
+    assert_fails(12, "start-A\nA-B\nB-end\n");
+    assert_fails(13, "6,10\n0,14\n\nfold along y=3\n");
+    assert_fails(14, "NNCB\n\nCH -> B\n");
+    assert_fails(15, "116\n13\n");
+    assert_fails(16, "8A004A801A8002F47\n");
+    assert_fails(16, "38006F45\n");
+    assert_fails(16, "D2FE2\n");
+}
+
+#[test]
+fn test_truncated_sub_packets() {
+    // The last sub-packet of a count operator is cut off.
+    assert_fails(16, "EE00D40C82\n");
+    // A length operator of 26 bits whose sub-packets take 27.
+    assert_fails(16, "38006B45291200\n");
+}
+
+/// A transmission of `depth` operators nested inside each other around a literal.
+fn nested_transmission(depth: usize) -> String {
+    // Version 0, type 0, one sub-packet counted in 11 bits.
+    let mut bits = "000000100000000001".repeat(depth);
+    bits.push_str("00010000001");
+    while !bits.len().is_multiple_of(8) {
+        bits.push('0');
+    }
+
+    let mut hex: String = bits
+        .as_bytes()
+        .chunks(4)
+        .map(|nibble| {
+            let v = nibble.iter().fold(0, |v, b| v << 1 | (b - b'0') as u32);
+            char::from_digit(v, 16).unwrap().to_ascii_uppercase()
+        })
+        .collect();
+    hex.push('\n');
+    hex
+}
+
+#[test]
+fn test_deeply_nested_packets() {
+    let runner = adventofcode2021::solver::find(16).unwrap();
+
+    assert_eq!(
+        ("0".to_owned(), "1".to_owned()),
+        run(runner.as_ref(), &nested_transmission(50)).unwrap()
+    );
+    assert!(matches!(
+        run(runner.as_ref(), &nested_transmission(200_000)),
+        Err(Error::Parse(_))
+    ));
+}
@@ -0,0 +1,229 @@
+//! Checks the solvers that take shortcuts against naive simulations of the puzzle on
+//! small random inputs.
+
+use adventofcode2021::{day14, day15, day6, day7};
+use proptest::collection::{hash_set, vec};
+use proptest::prelude::*;
+use std::collections::HashMap;
+
+/// Simulates every lanternfish individually.
+fn lanternfish(timers: &[u64], days: u32) -> u64 {
+    let mut fish = timers.to_vec();
+    for _ in 0..days {
+        let mut born = 0;
+        for timer in fish.iter_mut() {
+            if *timer == 0 {
+                *timer = 6;
+                born += 1;
+            } else {
+                *timer -= 1;
+            }
+        }
+        fish.extend(std::iter::repeat_n(8, born));
+    }
+
+    fish.len() as u64
+}
+
+/// Tries every position between the outermost crabs.
+fn cheapest_alignment(crabs: &[u32], cost: impl Fn(u64) -> u64) -> u64 {
+    let min = *crabs.iter().min().unwrap();
+    let max = *crabs.iter().max().unwrap();
+
+    (min..=max)
+        .map(|target| {
+            crabs
+                .iter()
+                .map(|crab| cost((*crab as i64 - target as i64).unsigned_abs()))
+                .sum()
+        })
+        .min()
+        .unwrap()
+}
+
+/// Builds the polymer string step by step.
+fn polymer(template: &str, rules: &HashMap<(char, char), char>, steps: usize) -> u64 {
+    let mut polymer: Vec<char> = template.chars().collect();
+    for _ in 0..steps {
+        let mut next = vec![polymer[0]];
+        for pair in polymer.windows(2) {
+            next.push(rules[&(pair[0], pair[1])]);
+            next.push(pair[1]);
+        }
+        polymer = next;
+    }
+
+    let mut counts: HashMap<char, u64> = HashMap::new();
+    for c in polymer {
+        *counts.entry(c).or_insert(0) += 1;
+    }
+
+    counts.values().max().unwrap() - counts.values().min().unwrap()
+}
+
+/// Walks every simple path from the top left to the bottom right corner.
+fn lowest_risk_exhaustive(grid: &[Vec<u8>]) -> u32 {
+    fn walk(
+        grid: &[Vec<u8>],
+        (x, y): (usize, usize),
+        risk: u32,
+        visited: &mut Vec<Vec<bool>>,
+        best: &mut u32,
+    ) {
+        if (x, y) == (grid.len() - 1, grid[0].len() - 1) {
+            *best = (*best).min(risk);
+            return;
+        }
+        visited[x][y] = true;
+        let neighbours = [
+            (x.wrapping_sub(1), y),
+            (x + 1, y),
+            (x, y.wrapping_sub(1)),
+            (x, y + 1),
+        ];
+        for (nx, ny) in neighbours {
+            if nx < grid.len() && ny < grid[0].len() && !visited[nx][ny] {
+                walk(grid, (nx, ny), risk + grid[nx][ny] as u32, visited, best);
+            }
+        }
+        visited[x][y] = false;
+    }
+
+    let mut visited = vec![vec![false; grid[0].len()]; grid.len()];
+    let mut best = u32::MAX;
+    walk(grid, (0, 0), 0, &mut visited, &mut best);
+
+    best
+}
+
+/// Relaxes the risk of every cell against its neighbours until nothing changes.
+fn lowest_risk_relaxed(grid: &[Vec<u8>]) -> u32 {
+    let (rows, cols) = (grid.len(), grid[0].len());
+    let mut risk = vec![vec![u32::MAX; cols]; rows];
+    risk[0][0] = 0;
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for x in 0..rows {
+            for y in 0..cols {
+                let neighbours = [
+                    (x.wrapping_sub(1), y),
+                    (x + 1, y),
+                    (x, y.wrapping_sub(1)),
+                    (x, y + 1),
+                ];
+                for (nx, ny) in neighbours {
+                    if nx < rows && ny < cols && risk[nx][ny] != u32::MAX {
+                        let via = risk[nx][ny] + grid[x][y] as u32;
+                        if via < risk[x][y] {
+                            risk[x][y] = via;
+                            changed = true;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    risk[rows - 1][cols - 1]
+}
+
+/// Repeats the grid five times in both directions, every tile one riskier than the one
+/// to its left or above, wrapping from 9 back to 1.
+fn five_times(grid: &[Vec<u8>]) -> Vec<Vec<u8>> {
+    let mut tiled = vec![];
+    for tile_row in 0..5 {
+        for row in grid {
+            let mut line = vec![];
+            for tile_column in 0..5 {
+                for risk in row {
+                    let mut risk = *risk;
+                    for _ in 0..tile_row + tile_column {
+                        risk = if risk == 9 { 1 } else { risk + 1 };
+                    }
+                    line.push(risk);
+                }
+            }
+            tiled.push(line);
+        }
+    }
+
+    tiled
+}
+
+fn risk_grid(max_side: usize) -> impl Strategy<Value = Vec<Vec<u8>>> {
+    (1..=max_side, 1..=max_side).prop_flat_map(|(rows, cols)| vec(vec(1u8..=9, cols), rows))
+}
+
+/// A polymer template over a small alphabet with an insertion rule for every pair.
+fn polymer_input() -> impl Strategy<Value = String> {
+    hash_set(prop::char::range('A', 'F'), 1..=4).prop_flat_map(|letters| {
+        let letters: Vec<char> = letters.into_iter().collect();
+        let pairs: Vec<(char, char)> = letters
+            .iter()
+            .flat_map(|a| letters.iter().map(move |b| (*a, *b)))
+            .collect();
+        (
+            vec(prop::sample::select(letters.clone()), 1..=6),
+            vec(prop::sample::select(letters), pairs.len()),
+        )
+            .prop_map(move |(template, results)| {
+                let mut input: String = template.into_iter().collect();
+                input.push_str("\n\n");
+                for ((a, b), r) in pairs.iter().zip(results) {
+                    input.push_str(&format!("{}{} -> {}\n", a, b, r));
+                }
+                input
+            })
+    })
+}
+
+proptest! {
+    #[test]
+    fn test_day6_part1_matches_simulation(timers in vec(0u64..=8, 0..6)) {
+        prop_assert_eq!(
+            lanternfish(&timers, 80).to_string(),
+            day6::part1(&timers).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_day7_matches_exhaustive_search(crabs in vec(0u32..50, 1..20)) {
+        prop_assert_eq!(
+            cheapest_alignment(&crabs, |steps| steps).to_string(),
+            day7::part1(&crabs).unwrap()
+        );
+        prop_assert_eq!(
+            cheapest_alignment(&crabs, |steps| steps * (steps + 1) / 2).to_string(),
+            day7::part2(&crabs).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_day14_part1_matches_literal_polymer(input in polymer_input()) {
+        let state = day14::parse_input(&input).unwrap();
+        let template: String = state.start.iter().collect();
+
+        prop_assert_eq!(
+            polymer(&template, &state.templates, 10).to_string(),
+            day14::part1(&state).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_day15_part1_matches_exhaustive_search(grid in risk_grid(4)) {
+        prop_assert_eq!(
+            lowest_risk_exhaustive(&grid).to_string(),
+            day15::part1(&grid).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_day15_part2_matches_relaxation(grid in risk_grid(3)) {
+        prop_assert_eq!(
+            lowest_risk_relaxed(&five_times(&grid)).to_string(),
+            day15::part2(&grid).unwrap()
+        );
+    }
+}